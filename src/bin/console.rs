@@ -0,0 +1,27 @@
+use std::{env, fs, io, process};
+
+// Interactive step debugger for a day 8 program, see `help` at its prompt.
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let path = match args.as_slice() {
+        [] => "input/2020/day8.txt",
+        [flag, path] if flag == "--input" => path.as_str(),
+        _ => {
+            eprintln!("usage: console [--input FILE]");
+            process::exit(2);
+        }
+    };
+    let source = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("cannot read {}: {}", path, e);
+        process::exit(1);
+    });
+    let program = aoc2020::day8_debugger::parse(&source).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+    let stdin = io::stdin();
+    if let Err(e) = aoc2020::day8_debugger::run(&program, stdin.lock(), &mut io::stdout()) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
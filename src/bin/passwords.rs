@@ -0,0 +1,26 @@
+use std::{env, fs, process};
+
+// Checks day 2 passwords against any of the built-in policies, see `passwords --help`.
+fn main() {
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(|a| a.as_str()) == Some("--help") {
+        println!("{}", aoc2020::day2_cli::USAGE);
+        return;
+    }
+    let mut path = "input/2020/day2.txt".to_string();
+    if args.first().map(|a| a.as_str()) == Some("--input") && args.len() > 1 {
+        path = args.remove(1);
+        args.remove(0);
+    }
+    let input = fs::read_to_string(&path).unwrap_or_else(|e| {
+        eprintln!("cannot read {}: {}", path, e);
+        process::exit(1);
+    });
+    match aoc2020::day2_cli::run(&args, &input) {
+        Ok(output) => print!("{}", output),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use std::{env, io, process};
+
+// Checks XMAS numbers as they arrive on stdin, see `xmas --help`.
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(|a| a.as_str()) == Some("--help") {
+        println!("{}", aoc2020::day9_stream::USAGE);
+        return;
+    }
+    let stdin = io::stdin();
+    if let Err(e) = aoc2020::day9_stream::run(&args, stdin.lock(), &mut io::stdout()) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -1,47 +1,238 @@
+use std::collections::HashMap;
+
+pub mod subset_sum;
+
+#[derive(Debug, PartialEq)]
+pub struct KSumSolution {
+    pub indices: Vec<usize>,
+    pub values: Vec<i32>,
+}
+
+impl KSumSolution {
+    fn from_indices(input: &[i32], mut indices: Vec<usize>) -> Self {
+        indices.sort_unstable();
+        let values = indices.iter().map(|&i| input[i]).collect();
+        KSumSolution { indices, values }
+    }
+
+    fn product(&self) -> i32 {
+        self.values.iter().product()
+    }
+}
+
 #[aoc_generator(day1)]
 fn generator_input(input: &str) -> Vec<i32> {
     input.lines().map(|a| a.parse::<i32>().unwrap()).collect()
 }
 
-#[aoc(day1, part1)]
-fn part1(input: &[i32]) -> i32 {
-    for i in 0..input.len() {
-        for j in (i + 1)..input.len() {
-            if input[i] + input[j] == 2020 {
-                return input[i] * input[j];
-            }
+fn two_sum(input: &[i32], target: i64) -> Option<KSumSolution> {
+    let mut seen: HashMap<i64, usize> = HashMap::new();
+    for (j, &value) in input.iter().enumerate() {
+        if let Some(&i) = seen.get(&(target - value as i64)) {
+            return Some(KSumSolution::from_indices(input, vec![i, j]));
         }
+        seen.entry(value as i64).or_insert(j);
     }
-    return 0;
+    None
 }
 
-#[aoc(day1, part2)]
-fn part2(input: &[i32]) -> i32 {
-    for i in 0..input.len() {
-        for j in (i + 1)..input.len() {
-            for k in (j + 1)..input.len() {
-                if input[i] + input[j] + input[k] == 2020 {
-                    return input[i] * input[j] * input[k];
-                }
+// `sorted` holds (value, original index) pairs in ascending value order. The innermost two
+// entries are found with two pointers, every other entry is fixed by recursing one level.
+fn k_sum_sorted(sorted: &[(i64, usize)], target: i64, k: usize) -> Option<Vec<usize>> {
+    if sorted.len() < k {
+        return None;
+    }
+    if k == 2 {
+        let (mut lo, mut hi) = (0, sorted.len() - 1);
+        while lo < hi {
+            let sum = sorted[lo].0 + sorted[hi].0;
+            if sum == target {
+                return Some(vec![sorted[lo].1, sorted[hi].1]);
+            } else if sum < target {
+                lo += 1;
+            } else {
+                hi -= 1;
             }
         }
+        return None;
+    }
+    for i in 0..=sorted.len() - k {
+        // Equal values lead to the same sub-problem, so only the first of a run is tried.
+        if i > 0 && sorted[i].0 == sorted[i - 1].0 {
+            continue;
+        }
+        if let Some(mut indices) = k_sum_sorted(&sorted[i + 1..], target - sorted[i].0, k - 1) {
+            indices.push(sorted[i].1);
+            return Some(indices);
+        }
+    }
+    None
+}
+
+fn k_sum(input: &[i32], target: i32, k: usize) -> Option<KSumSolution> {
+    let target = target as i64;
+    match k {
+        0 if target == 0 => Some(KSumSolution::from_indices(input, vec![])),
+        0 => None,
+        1 => input
+            .iter()
+            .position(|&value| value as i64 == target)
+            .map(|i| KSumSolution::from_indices(input, vec![i])),
+        2 => two_sum(input, target),
+        _ => {
+            let mut sorted = input
+                .iter()
+                .enumerate()
+                .map(|(i, &value)| (value as i64, i))
+                .collect::<Vec<_>>();
+            sorted.sort_unstable();
+            k_sum_sorted(&sorted, target, k)
+                .map(|indices| KSumSolution::from_indices(input, indices))
+        }
     }
-    return 0;
+}
+
+#[aoc(day1, part1)]
+fn part1(input: &[i32]) -> Option<i32> {
+    k_sum(input, 2020, 2).map(|solution| solution.product())
+}
+
+#[aoc(day1, part2)]
+fn part2(input: &[i32]) -> Option<i32> {
+    k_sum(input, 2020, 3).map(|solution| solution.product())
 }
 
 #[cfg(test)]
 pub mod tests {
-    use super::{part1, part2};
+    use super::{k_sum, part1, part2, KSumSolution};
+
+    fn brute_force_pair(input: &[i32], target: i32) -> Option<(i32, i32)> {
+        for i in 0..input.len() {
+            for j in (i + 1)..input.len() {
+                if input[i] + input[j] == target {
+                    return Some((input[i], input[j]));
+                }
+            }
+        }
+        None
+    }
+
+    fn brute_force_triple(input: &[i32], target: i32) -> Option<(i32, i32, i32)> {
+        for i in 0..input.len() {
+            for j in (i + 1)..input.len() {
+                for k in (j + 1)..input.len() {
+                    if input[i] + input[j] + input[k] == target {
+                        return Some((input[i], input[j], input[k]));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn assert_valid(input: &[i32], target: i32, k: usize, solution: &KSumSolution) {
+        assert_eq!(solution.indices.len(), k);
+        assert!(solution.indices.windows(2).all(|w| w[0] < w[1]));
+        for (&i, &value) in solution.indices.iter().zip(&solution.values) {
+            assert_eq!(input[i], value);
+        }
+        assert_eq!(solution.values.iter().sum::<i32>(), target);
+    }
 
     #[test]
     fn day1_part1() {
         let input = vec![1721, 979, 366, 299, 675, 1456];
-        assert_eq!(part1(&input), 514579);
+        assert_eq!(part1(&input), Some(514579));
     }
 
     #[test]
     fn day1_part2() {
         let input = vec![1721, 979, 366, 299, 675, 1456];
-        assert_eq!(part2(&input), 241861950);
+        assert_eq!(part2(&input), Some(241861950));
+    }
+
+    #[test]
+    fn k_sum_returns_indices_and_values() {
+        let input = vec![1721, 979, 366, 299, 675, 1456];
+        assert_eq!(
+            k_sum(&input, 2020, 2),
+            Some(KSumSolution {
+                indices: vec![0, 3],
+                values: vec![1721, 299],
+            })
+        );
+        assert_eq!(
+            k_sum(&input, 2020, 3),
+            Some(KSumSolution {
+                indices: vec![1, 2, 4],
+                values: vec![979, 366, 675],
+            })
+        );
+    }
+
+    #[test]
+    fn k_sum_without_solution() {
+        let input = vec![1, 2, 3];
+        assert_eq!(k_sum(&input, 2020, 2), None);
+        assert_eq!(k_sum(&input, 2020, 3), None);
+        assert_eq!(k_sum(&input, 6, 4), None);
+        assert_eq!(part1(&input), None);
+        assert_eq!(part2(&input), None);
+    }
+
+    #[test]
+    fn k_sum_handles_duplicates() {
+        assert_eq!(k_sum(&[1010], 2020, 2), None);
+        assert_eq!(
+            k_sum(&[1010, 5, 1010], 2020, 2).unwrap().indices,
+            vec![0, 2]
+        );
+        assert_eq!(
+            k_sum(&[700, 620, 700], 2020, 3).unwrap().indices,
+            vec![0, 1, 2]
+        );
+        assert_eq!(k_sum(&[700, 620, 1], 2020, 3), None);
+    }
+
+    #[test]
+    fn k_sum_arbitrary_arity() {
+        let input = vec![5, -3, 12, 8, 1, 7, 4];
+        for k in 0..=input.len() {
+            for target in -5..40 {
+                if let Some(solution) = k_sum(&input, target, k) {
+                    assert_valid(&input, target, k, &solution);
+                }
+            }
+        }
+        assert!(k_sum(&input, 34, 7).is_some());
+        assert!(k_sum(&input, 0, 0).is_some());
+    }
+
+    #[test]
+    fn k_sum_matches_brute_force() {
+        let mut seed = 17_u32;
+        for _ in 0..50 {
+            let input = (0..30)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                    (seed >> 16) as i32 % 1500
+                })
+                .collect::<Vec<_>>();
+            for target in &[1000, 2020, 2500] {
+                let pair = k_sum(&input, *target, 2);
+                assert_eq!(pair.is_some(), brute_force_pair(&input, *target).is_some());
+                if let Some(solution) = pair {
+                    assert_valid(&input, *target, 2, &solution);
+                }
+                let triple = k_sum(&input, *target, 3);
+                assert_eq!(
+                    triple.is_some(),
+                    brute_force_triple(&input, *target).is_some()
+                );
+                if let Some(solution) = triple {
+                    assert_valid(&input, *target, 3, &solution);
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,220 @@
+use std::fmt;
+
+pub use super::KSumSolution;
+
+// Upper bound on the number of DP cells (items x reachable sums) a search may allocate.
+const DEFAULT_STATE_LIMIT: usize = 1 << 26;
+
+#[derive(Debug, PartialEq)]
+pub enum SubsetSumError {
+    SearchSpaceTooLarge { states: usize, limit: usize },
+    CountOverflow,
+}
+
+impl fmt::Display for SubsetSumError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SubsetSumError::SearchSpaceTooLarge { states, limit } => write!(
+                f,
+                "the search needs {} states, more than the limit of {}",
+                states, limit
+            ),
+            SubsetSumError::CountOverflow => {
+                write!(f, "the number of subsets does not fit in 128 bits")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SubsetSumError {}
+
+// The window of sums that can still lead to the target. For non-negative inputs nothing above the
+// target is worth tracking, otherwise every sum between the negative and the positive total is.
+fn sum_window(input: &[i32], target: i64) -> Option<(i64, i64)> {
+    let negatives = input
+        .iter()
+        .filter(|&&v| v < 0)
+        .map(|&v| v as i64)
+        .sum::<i64>();
+    let positives = input
+        .iter()
+        .filter(|&&v| v > 0)
+        .map(|&v| v as i64)
+        .sum::<i64>();
+    if target < negatives || target > positives {
+        return None;
+    }
+    if negatives == 0 {
+        Some((0, target))
+    } else {
+        Some((negatives, positives))
+    }
+}
+
+fn check_states(items: usize, width: usize, limit: usize) -> Result<(), SubsetSumError> {
+    match items.checked_mul(width) {
+        Some(states) if states <= limit => Ok(()),
+        states => Err(SubsetSumError::SearchSpaceTooLarge {
+            states: states.unwrap_or(usize::MAX),
+            limit,
+        }),
+    }
+}
+
+pub fn find_subset(input: &[i32], target: i32) -> Result<Option<KSumSolution>, SubsetSumError> {
+    find_subset_bounded(input, target, DEFAULT_STATE_LIMIT)
+}
+
+fn find_subset_bounded(
+    input: &[i32],
+    target: i32,
+    limit: usize,
+) -> Result<Option<KSumSolution>, SubsetSumError> {
+    let target = target as i64;
+    let (low, high) = match sum_window(input, target) {
+        Some(window) => window,
+        None => return Ok(None),
+    };
+    let width = (high - low + 1) as usize;
+    check_states(input.len() + 1, width, limit)?;
+
+    // reachable[i][s - low] tells whether the sum s can be built from the first i entries.
+    let mut reachable = vec![vec![false; width]; input.len() + 1];
+    reachable[0][(-low) as usize] = true;
+    for (i, &value) in input.iter().enumerate() {
+        let (previous, next) = reachable.split_at_mut(i + 1);
+        let (previous, next) = (&previous[i], &mut next[0]);
+        for (offset, &is_reachable) in previous.iter().enumerate() {
+            if !is_reachable {
+                continue;
+            }
+            next[offset] = true;
+            let sum = offset as i64 + low + value as i64;
+            if low <= sum && sum <= high {
+                next[(sum - low) as usize] = true;
+            }
+        }
+    }
+
+    if !reachable[input.len()][(target - low) as usize] {
+        return Ok(None);
+    }
+    let mut indices = vec![];
+    let mut sum = target;
+    for i in (0..input.len()).rev() {
+        if !reachable[i][(sum - low) as usize] {
+            indices.push(i);
+            sum -= input[i] as i64;
+        }
+    }
+    Ok(Some(KSumSolution::from_indices(input, indices)))
+}
+
+// Counts every subset of entries (by index, including the empty one) whose sum is the target.
+pub fn count_subsets(input: &[i32], target: i32) -> Result<u128, SubsetSumError> {
+    count_subsets_bounded(input, target, DEFAULT_STATE_LIMIT)
+}
+
+fn count_subsets_bounded(input: &[i32], target: i32, limit: usize) -> Result<u128, SubsetSumError> {
+    let target = target as i64;
+    let (low, high) = match sum_window(input, target) {
+        Some(window) => window,
+        None => return Ok(0),
+    };
+    let width = (high - low + 1) as usize;
+    check_states(input.len() + 1, width, limit)?;
+
+    let mut counts = vec![0_u128; width];
+    counts[(-low) as usize] = 1;
+    for &value in input {
+        let mut next = counts.clone();
+        for (offset, &count) in counts.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let sum = offset as i64 + low + value as i64;
+            if low <= sum && sum <= high {
+                let slot = &mut next[(sum - low) as usize];
+                *slot = slot
+                    .checked_add(count)
+                    .ok_or(SubsetSumError::CountOverflow)?;
+            }
+        }
+        counts = next;
+    }
+    Ok(counts[(target - low) as usize])
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{count_subsets, find_subset, find_subset_bounded, SubsetSumError};
+
+    fn brute_force_count(input: &[i32], target: i32) -> u128 {
+        (0..1_u32 << input.len())
+            .filter(|mask| {
+                (0..input.len())
+                    .filter(|i| mask & (1 << i) != 0)
+                    .map(|i| input[i])
+                    .sum::<i32>()
+                    == target
+            })
+            .count() as u128
+    }
+
+    #[test]
+    fn finds_witness_subset() {
+        let input = vec![1721, 979, 366, 299, 675, 1456];
+        let subset = find_subset(&input, 2020).unwrap().unwrap();
+        assert_eq!(subset.values.iter().sum::<i32>(), 2020);
+        for (&i, &value) in subset.indices.iter().zip(&subset.values) {
+            assert_eq!(input[i], value);
+        }
+        assert_eq!(find_subset(&input, 1).unwrap(), None);
+        assert_eq!(find_subset(&input, 0).unwrap().unwrap().indices, vec![]);
+    }
+
+    #[test]
+    fn finds_subset_with_negative_entries() {
+        let input = vec![8, -3, 5, -7, 2];
+        for target in -10..=15 {
+            let subset = find_subset(&input, target).unwrap();
+            assert_eq!(subset.is_some(), brute_force_count(&input, target) > 0);
+            if let Some(subset) = subset {
+                assert_eq!(subset.values.iter().sum::<i32>(), target);
+            }
+        }
+    }
+
+    #[test]
+    fn counts_all_subsets() {
+        let input = vec![1721, 979, 366, 299, 675, 1456];
+        assert_eq!(count_subsets(&input, 2020).unwrap(), 2);
+        let input = vec![3, 3, -2, 5, 0, 1, -1, 4];
+        for target in -4..=16 {
+            assert_eq!(
+                count_subsets(&input, target).unwrap(),
+                brute_force_count(&input, target)
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_search() {
+        assert_eq!(
+            find_subset_bounded(&[1, 2, 3], 6, 10),
+            Err(SubsetSumError::SearchSpaceTooLarge {
+                states: 4 * 7,
+                limit: 10
+            })
+        );
+    }
+
+    #[test]
+    fn reports_count_overflow() {
+        assert_eq!(
+            count_subsets(&[0; 128], 0),
+            Err(SubsetSumError::CountOverflow)
+        );
+        assert_eq!(count_subsets(&[0; 127], 0), Ok(1 << 127));
+    }
+}
@@ -1,108 +1,203 @@
 use std::collections::HashMap;
+use std::fmt;
+
+use chains::Arrangements;
+
+pub mod chains;
 
 #[aoc_generator(day10)]
 fn generator_input(input: &str) -> Vec<i32> {
     input.lines().map(|a| a.parse::<i32>().unwrap()).collect()
 }
 
-#[aoc(day10, part1)]
-fn part1(input: &[i32]) -> i32 {
-    let mut input = input.to_vec();
-    input.sort();
-    let mut last_joltage = 0;
-    let mut diff_1_count = 0;
-    let mut diff_3_count = 1; // That last difference will always be three, as it is to our device
-    for joltage in input.iter() {
-        match joltage - last_joltage {
-            3 => {
-                diff_3_count += 1;
+const DEFAULT_DIFFERENCES: [i32; 3] = [1, 2, 3];
+
+#[derive(Debug, PartialEq)]
+pub enum ChainError {
+    NoDifferences,
+    InvalidDifference(i32),
+    InvalidAdapter(i32),
+    // No chain of adapters gets from the outlet to the device.
+    Unreachable { reached: i32, device: i32 },
+    // The device's rating does not fit in an i32.
+    DeviceOutOfRange { highest: i32, difference: i32 },
+    Overflow,
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChainError::NoDifferences => write!(f, "no joltage differences are allowed"),
+            ChainError::InvalidDifference(difference) => write!(
+                f,
+                "joltage differences must be positive, not {}",
+                difference
+            ),
+            ChainError::InvalidAdapter(joltage) => {
+                write!(f, "adapter joltages must be positive, not {}", joltage)
             }
-            1 => {
-                diff_1_count += 1;
+            ChainError::Unreachable { reached, device } => write!(
+                f,
+                "no chain reaches the device at {} jolts, the highest reachable joltage is {}",
+                device, reached
+            ),
+            ChainError::DeviceOutOfRange {
+                highest,
+                difference,
+            } => write!(
+                f,
+                "a device {} jolts above the highest adapter at {} jolts is out of range",
+                difference, highest
+            ),
+            ChainError::Overflow => {
+                write!(f, "the number of arrangements does not fit in 128 bits")
             }
-            _ => {}
         }
-        last_joltage = *joltage;
     }
-    diff_1_count * diff_3_count
 }
 
-fn calc_number_of_arrangements_if_possible(
-    input: &[i32],
-    i: usize,
-    j: usize,
-    memo: &mut HashMap<usize, i32>,
-) -> i32 {
-    if input[j] - input[i] <= 3 {
-        return calc_number_of_arrangements(&input, j, memo);
+impl std::error::Error for ChainError {}
+
+// The outlet, the adapters in order and the device, which is rated the largest allowed
+// difference above the highest adapter.
+fn chain_joltages(adapters: &[i32], differences: &[i32]) -> Result<Vec<i32>, ChainError> {
+    if let Some(&difference) = differences.iter().find(|&&d| d < 1) {
+        return Err(ChainError::InvalidDifference(difference));
+    }
+    let largest = *differences.iter().max().ok_or(ChainError::NoDifferences)?;
+    if let Some(&joltage) = adapters.iter().find(|&&joltage| joltage < 1) {
+        return Err(ChainError::InvalidAdapter(joltage));
     }
-    0
+    let mut joltages = vec![0];
+    joltages.extend_from_slice(adapters);
+    joltages.sort_unstable();
+    let highest = *joltages.last().unwrap();
+    let device = highest
+        .checked_add(largest)
+        .ok_or(ChainError::DeviceOutOfRange {
+            highest,
+            difference: largest,
+        })?;
+    joltages.push(device);
+    Ok(joltages)
 }
 
-fn calc_number_of_arrangements(input: &[i32], i: usize, memo: &mut HashMap<usize, i32>) -> i32 {
-    if memo.contains_key(&i) {
-        return memo[&i];
+// How often each difference between consecutive joltages occurs when every adapter is used.
+fn difference_histogram(
+    adapters: &[i32],
+    differences: &[i32],
+) -> Result<HashMap<i32, usize>, ChainError> {
+    let mut histogram = HashMap::new();
+    for pair in chain_joltages(adapters, differences)?.windows(2) {
+        *histogram.entry(pair[1] - pair[0]).or_insert(0) += 1;
     }
+    Ok(histogram)
+}
 
-    let count = match input.len() - i {
-        0 => 0,
-        1 => 1,
-        2 => calc_number_of_arrangements_if_possible(input, i, i + 1, memo),
-        3 => {
-            calc_number_of_arrangements_if_possible(input, i, i + 1, memo)
-                + calc_number_of_arrangements_if_possible(input, i, i + 2, memo)
-        }
-        _ => {
-            calc_number_of_arrangements_if_possible(input, i, i + 1, memo)
-                + calc_number_of_arrangements_if_possible(input, i, i + 2, memo)
-                + calc_number_of_arrangements_if_possible(input, i, i + 3, memo)
-        }
-    };
+#[aoc(day10, part1)]
+fn part1(input: &[i32]) -> Result<usize, ChainError> {
+    let histogram = difference_histogram(input, &DEFAULT_DIFFERENCES)?;
+    Ok(histogram.get(&1).unwrap_or(&0) * histogram.get(&3).unwrap_or(&0))
+}
 
-    memo.insert(i, count);
-    count
+// How many ways there are to get from the outlet to the device.
+fn count_arrangements(adapters: &[i32], differences: &[i32]) -> Result<u128, ChainError> {
+    Arrangements::new(adapters, differences)?.count()
 }
 
 #[aoc(day10, part2)]
-fn part2(input: &[i32]) -> i32 {
-    let mut input = input.to_vec();
-    input.push(0);
-    input.sort();
-    input.push(input.last().unwrap() + 3);
-    calc_number_of_arrangements(&input, 0, &mut HashMap::<usize, i32>::new())
+fn part2(input: &[i32]) -> Result<u128, ChainError> {
+    count_arrangements(input, &DEFAULT_DIFFERENCES)
 }
 
 #[cfg(test)]
 pub mod tests {
-    use super::{part1, part2};
+    use super::{count_arrangements, difference_histogram, part1, part2, ChainError};
+
+    pub static SMALL: [i32; 11] = [16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
+    pub static LARGE: [i32; 31] = [
+        28, 33, 18, 42, 31, 14, 46, 20, 48, 47, 24, 23, 49, 45, 19, 38, 39, 11, 1, 32, 25, 35, 8,
+        17, 7, 9, 4, 2, 34, 10, 3,
+    ];
 
     #[test]
     fn day10_part1_small() {
-        let input = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
-        assert_eq!(part1(&input), 35);
+        assert_eq!(part1(&SMALL), Ok(35));
     }
 
     #[test]
     fn day10_part1_large() {
-        let input = vec![
-            28, 33, 18, 42, 31, 14, 46, 20, 48, 47, 24, 23, 49, 45, 19, 38, 39, 11, 1, 32, 25, 35,
-            8, 17, 7, 9, 4, 2, 34, 10, 3,
-        ];
-        assert_eq!(part1(&input), 220);
+        assert_eq!(part1(&LARGE), Ok(220));
     }
 
     #[test]
     fn day10_part2_small() {
-        let input = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
-        assert_eq!(part2(&input), 8);
+        assert_eq!(part2(&SMALL), Ok(8));
     }
 
     #[test]
     fn day10_part2_large() {
-        let input = vec![
-            28, 33, 18, 42, 31, 14, 46, 20, 48, 47, 24, 23, 49, 45, 19, 38, 39, 11, 1, 32, 25, 35,
-            8, 17, 7, 9, 4, 2, 34, 10, 3,
-        ];
-        assert_eq!(part2(&input), 19208);
+        assert_eq!(part2(&LARGE), Ok(19208));
+    }
+
+    #[test]
+    fn arrangements_beyond_32_bits() {
+        let input = (1..=100).collect::<Vec<_>>();
+        assert_eq!(part2(&input), Ok(180_396_380_815_100_901_214_157_639));
+        let input = (1..=200).collect::<Vec<_>>();
+        assert_eq!(part2(&input), Err(ChainError::Overflow));
+    }
+
+    #[test]
+    fn allowed_differences() {
+        assert_eq!(count_arrangements(&SMALL, &[1, 3]), Ok(2));
+        assert_eq!(count_arrangements(&SMALL, &[4, 1, 2, 3]), Ok(170));
+        assert_eq!(count_arrangements(&[2, 4, 6, 8], &[2]), Ok(1));
+        assert_eq!(
+            count_arrangements(&[1, 2, 6, 7], &[1, 2, 3]),
+            Err(ChainError::Unreachable {
+                reached: 2,
+                device: 10
+            })
+        );
+        assert_eq!(
+            count_arrangements(&[1, 3, 5], &[2])
+                .unwrap_err()
+                .to_string(),
+            "no chain reaches the device at 7 jolts, the highest reachable joltage is 0"
+        );
+        assert_eq!(
+            count_arrangements(&SMALL, &[]),
+            Err(ChainError::NoDifferences)
+        );
+        assert_eq!(
+            count_arrangements(&SMALL, &[3, 0]),
+            Err(ChainError::InvalidDifference(0))
+        );
+        assert_eq!(
+            count_arrangements(&[3, -1], &[1, 2, 3]),
+            Err(ChainError::InvalidAdapter(-1))
+        );
+        assert_eq!(
+            part1(&[1, i32::MAX - 1]).unwrap_err().to_string(),
+            "a device 3 jolts above the highest adapter at 2147483646 jolts is out of range"
+        );
+        assert_eq!(
+            count_arrangements(&[i32::MAX - 2], &[1, 2]),
+            Err(ChainError::Unreachable {
+                reached: 0,
+                device: i32::MAX
+            })
+        );
+    }
+
+    #[test]
+    fn histogram() {
+        let histogram = difference_histogram(&LARGE, &[1, 2, 3]).unwrap();
+        assert_eq!(histogram.len(), 2);
+        assert_eq!((histogram[&1], histogram[&3]), (22, 10));
+        let histogram = difference_histogram(&[2, 4, 5], &[1, 2]).unwrap();
+        assert_eq!((histogram[&1], histogram[&2]), (1, 3));
+        assert_eq!(part1(&[]), Ok(0));
     }
 }
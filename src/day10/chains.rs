@@ -0,0 +1,308 @@
+use super::chain_joltages;
+pub use super::ChainError;
+
+// All the ways to get from the outlet to the device. Joltages are indexed in sorted order, with the
+// outlet first and the device last, so every step goes to a higher index.
+pub struct Arrangements {
+    joltages: Vec<i32>,
+    differences: Vec<i32>,
+    // How many chains lead from each joltage to the device, `None` where that does not fit in a
+    // u128.
+    counts: Vec<Option<u128>>,
+}
+
+impl Arrangements {
+    pub fn new(adapters: &[i32], differences: &[i32]) -> Result<Self, ChainError> {
+        let joltages = chain_joltages(adapters, differences)?;
+        let mut arrangements = Arrangements {
+            counts: vec![Some(0); joltages.len()],
+            joltages,
+            differences: differences.to_vec(),
+        };
+        let device = arrangements.device();
+        arrangements.counts[device] = Some(1);
+        for i in (0..device).rev() {
+            let count = arrangements
+                .successors(i)
+                .try_fold(0u128, |count, j| count.checked_add(arrangements.counts[j]?));
+            arrangements.counts[i] = count;
+        }
+        Ok(arrangements)
+    }
+
+    // How many chains there are in all.
+    pub fn count(&self) -> Result<u128, ChainError> {
+        match self.counts[0] {
+            None => Err(ChainError::Overflow),
+            Some(0) => {
+                let reached = self.reachable().iter().rposition(|&r| r).unwrap();
+                Err(ChainError::Unreachable {
+                    reached: self.joltages[reached],
+                    device: self.joltages[self.device()],
+                })
+            }
+            Some(count) => Ok(count),
+        }
+    }
+
+    fn device(&self) -> usize {
+        self.joltages.len() - 1
+    }
+
+    // The joltages reachable in one step from `i`, in increasing order.
+    fn successors(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        let largest = *self.differences.iter().max().unwrap();
+        (i + 1..self.joltages.len())
+            .take_while(move |&j| self.joltages[j] - self.joltages[i] <= largest)
+            .filter(move |&j| {
+                self.differences
+                    .contains(&(self.joltages[j] - self.joltages[i]))
+            })
+    }
+
+    fn leads_to_device(&self, i: usize) -> bool {
+        self.counts[i] != Some(0)
+    }
+
+    // Which joltages some chain from the outlet gets to, whether or not it goes on to the device.
+    fn reachable(&self) -> Vec<bool> {
+        let mut reached = vec![false; self.joltages.len()];
+        reached[0] = true;
+        for i in 0..self.device() {
+            if reached[i] {
+                for j in self.successors(i) {
+                    reached[j] = true;
+                }
+            }
+        }
+        reached
+    }
+
+    // The first joltage after `after` that follows `i` and still leads to the device.
+    fn next_step(&self, i: usize, after: usize) -> Option<usize> {
+        self.successors(i)
+            .find(|&j| j > after && self.leads_to_device(j))
+    }
+
+    fn adapters(&self, path: &[usize]) -> Vec<i32> {
+        path[1..path.len() - 1]
+            .iter()
+            .map(|&i| self.joltages[i])
+            .collect()
+    }
+
+    // Every chain as the adapters it uses, in lexicographic order.
+    pub fn chains(&self) -> Chains<'_> {
+        Chains {
+            arrangements: self,
+            path: vec![],
+            started: false,
+        }
+    }
+
+    // The chain `chains().nth(k)` would return, without going through the ones before it.
+    pub fn kth(&self, mut k: u128) -> Option<Vec<i32>> {
+        if self.counts[0].is_some_and(|count| k >= count) {
+            return None;
+        }
+        let mut path = vec![0];
+        let mut i = 0;
+        while i != self.device() {
+            for j in self.successors(i) {
+                match self.counts[j] {
+                    Some(count) if k >= count => k -= count,
+                    _ => {
+                        i = j;
+                        break;
+                    }
+                }
+            }
+            path.push(i);
+        }
+        Some(self.adapters(&path))
+    }
+
+    // The chain with the fewest adapters, as the adapters it keeps. Every adapter not on it can be
+    // taken out at once. Ties go to the lexicographically smallest chain.
+    pub fn shortest_chain(&self) -> Option<Vec<i32>> {
+        let device = self.device();
+        let mut steps = vec![None; self.joltages.len()];
+        let mut next = vec![device; self.joltages.len()];
+        steps[device] = Some(0);
+        for i in (0..device).rev() {
+            for j in self.successors(i) {
+                if let Some(s) = steps[j] {
+                    if steps[i].is_none_or(|best| s + 1 < best) {
+                        steps[i] = Some(s + 1);
+                        next[i] = j;
+                    }
+                }
+            }
+        }
+        steps[0]?;
+        let mut path = vec![0];
+        while *path.last().unwrap() != device {
+            path.push(next[*path.last().unwrap()]);
+        }
+        Some(self.adapters(&path))
+    }
+
+    // Each adapter in sorted order and whether every chain uses it. An adapter is mandatory
+    // exactly when it is on some chain and no step of a chain jumps over it.
+    pub fn mandatory(&self) -> Vec<(i32, bool)> {
+        let device = self.device();
+        let reached = self.reachable();
+        // +1 where a step jumps over a stretch of joltages, -1 where it lands.
+        let mut jumps = vec![0i64; self.joltages.len() + 1];
+        for i in (0..device).filter(|&i| reached[i]) {
+            for j in self.successors(i).filter(|&j| self.leads_to_device(j)) {
+                jumps[i + 1] += 1;
+                jumps[j] -= 1;
+            }
+        }
+        let mut jumped = 0;
+        (1..device)
+            .map(|i| {
+                jumped += jumps[i];
+                let on_chain = reached[i] && self.leads_to_device(i);
+                (self.joltages[i], on_chain && jumped == 0)
+            })
+            .collect()
+    }
+}
+
+// Depth first over the steps, trying lower joltages first and never entering a dead end.
+pub struct Chains<'a> {
+    arrangements: &'a Arrangements,
+    path: Vec<usize>,
+    started: bool,
+}
+
+impl<'a> Iterator for Chains<'a> {
+    type Item = Vec<i32>;
+
+    fn next(&mut self) -> Option<Vec<i32>> {
+        let arrangements = self.arrangements;
+        if !self.started {
+            self.started = true;
+            if !arrangements.leads_to_device(0) {
+                return None;
+            }
+            self.path.push(0);
+        } else {
+            loop {
+                let last = self.path.pop()?;
+                let &parent = self.path.last()?;
+                if let Some(j) = arrangements.next_step(parent, last) {
+                    self.path.push(j);
+                    break;
+                }
+            }
+        }
+        while let Some(&i) = self.path.last().filter(|&&i| i != arrangements.device()) {
+            self.path.push(arrangements.next_step(i, i).unwrap());
+        }
+        Some(arrangements.adapters(&self.path))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{Arrangements, ChainError};
+    use crate::day10::tests::{LARGE, SMALL};
+    use crate::day10::DEFAULT_DIFFERENCES;
+
+    #[test]
+    fn chains_in_order() {
+        let arrangements = Arrangements::new(&SMALL, &DEFAULT_DIFFERENCES).unwrap();
+        let chains = arrangements.chains().collect::<Vec<_>>();
+        assert_eq!(chains.len(), 8);
+        assert_eq!(chains[0], vec![1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19]);
+        assert_eq!(chains[3], vec![1, 4, 5, 7, 10, 12, 15, 16, 19]);
+        assert_eq!(chains[7], vec![1, 4, 7, 10, 12, 15, 16, 19]);
+        let mut sorted = chains.clone();
+        sorted.sort();
+        assert_eq!(chains, sorted);
+        for (k, chain) in chains.iter().enumerate() {
+            assert_eq!(arrangements.kth(k as u128).as_ref(), Some(chain));
+        }
+        assert_eq!(arrangements.kth(8), None);
+
+        let arrangements = Arrangements::new(&LARGE, &DEFAULT_DIFFERENCES).unwrap();
+        assert_eq!(arrangements.chains().count(), 19208);
+        assert_eq!(arrangements.kth(1000), arrangements.chains().nth(1000));
+        assert_eq!(
+            arrangements.kth(1000).unwrap(),
+            vec![
+                1, 2, 3, 4, 7, 8, 10, 11, 14, 17, 19, 20, 23, 24, 25, 28, 31, 32, 34, 35, 38, 39,
+                42, 45, 48, 49
+            ]
+        );
+    }
+
+    #[test]
+    fn shortest_chain_and_mandatory_adapters() {
+        let arrangements = Arrangements::new(&SMALL, &DEFAULT_DIFFERENCES).unwrap();
+        assert_eq!(
+            arrangements.shortest_chain(),
+            Some(vec![1, 4, 7, 10, 12, 15, 16, 19])
+        );
+        let mandatory = arrangements
+            .mandatory()
+            .into_iter()
+            .filter(|&(_, mandatory)| mandatory)
+            .map(|(joltage, _)| joltage)
+            .collect::<Vec<_>>();
+        assert_eq!(mandatory, vec![1, 4, 7, 10, 12, 15, 16, 19]);
+
+        let arrangements = Arrangements::new(&LARGE, &DEFAULT_DIFFERENCES).unwrap();
+        assert_eq!(
+            arrangements.shortest_chain(),
+            Some(vec![
+                1, 4, 7, 8, 11, 14, 17, 20, 23, 25, 28, 31, 32, 35, 38, 39, 42, 45, 46, 49
+            ])
+        );
+        let mandatory = arrangements
+            .mandatory()
+            .into_iter()
+            .filter(|&(_, mandatory)| mandatory)
+            .map(|(joltage, _)| joltage)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            mandatory,
+            vec![4, 7, 11, 14, 17, 20, 23, 25, 28, 31, 35, 38, 39, 42, 45, 49]
+        );
+    }
+
+    #[test]
+    fn too_many_to_count() {
+        let adapters = (1..=200).collect::<Vec<_>>();
+        let arrangements = Arrangements::new(&adapters, &DEFAULT_DIFFERENCES).unwrap();
+        assert_eq!(arrangements.count(), Err(ChainError::Overflow));
+        assert_eq!(arrangements.kth(0), Some(adapters.clone()));
+        assert_eq!(arrangements.chains().next(), Some(adapters));
+        assert_eq!(
+            arrangements.shortest_chain().map(|chain| chain.len()),
+            Some(67)
+        );
+    }
+
+    #[test]
+    fn no_chain() {
+        let arrangements = Arrangements::new(&[1, 2, 6, 7], &DEFAULT_DIFFERENCES).unwrap();
+        assert_eq!(arrangements.chains().next(), None);
+        assert_eq!(arrangements.kth(0), None);
+        assert_eq!(arrangements.shortest_chain(), None);
+        assert_eq!(
+            arrangements.mandatory(),
+            vec![(1, false), (2, false), (6, false), (7, false)]
+        );
+        // With only one way through, every adapter on it is mandatory and the rest are not.
+        let arrangements = Arrangements::new(&[2, 3, 4], &[2]).unwrap();
+        assert_eq!(arrangements.chains().collect::<Vec<_>>(), vec![vec![2, 4]]);
+        assert_eq!(
+            arrangements.mandatory(),
+            vec![(2, true), (3, false), (4, true)]
+        );
+    }
+}
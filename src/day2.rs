@@ -1,3 +1,7 @@
+use std::fmt;
+
+pub mod cli;
+
 #[derive(Debug)]
 struct PasswordsWithPolicy {
     min: i32,
@@ -15,66 +19,322 @@ impl std::cmp::PartialEq for PasswordsWithPolicy {
     }
 }
 
-fn parse_pw_line(input: &str) -> PasswordsWithPolicy {
-    let parts = input.split(' ').collect::<Vec<&str>>();
-    let minmax = parts[0].split('-').collect::<Vec<&str>>();
-    let min = minmax[0].parse::<i32>().unwrap();
-    let max = minmax[1].parse::<i32>().unwrap();
-    let char = parts[1].chars().next().unwrap();
-    let pw = parts[2].to_string();
-    PasswordsWithPolicy { min, max, char, pw }
+impl PasswordsWithPolicy {
+    fn char_count(&self) -> i32 {
+        self.pw.chars().filter(|&c| c == self.char).count() as i32
+    }
+
+    // Positions are 1-based; anything outside the password has no character to match.
+    fn char_at(&self, pos: i32) -> Option<char> {
+        if pos < 1 {
+            return None;
+        }
+        self.pw.chars().nth((pos - 1) as usize)
+    }
+
+    // Number of the two positions holding the character, or `None` if either is out of range.
+    fn position_matches(&self) -> Option<usize> {
+        let first = self.char_at(self.min)?;
+        let second = self.char_at(self.max)?;
+        Some([first, second].iter().filter(|&&c| c == self.char).count())
+    }
+}
+
+trait PasswordPolicy {
+    fn name(&self) -> &'static str;
+    fn is_valid(&self, password: &PasswordsWithPolicy) -> bool;
 }
 
-#[aoc_generator(day2)]
-fn generator_input(input: &str) -> Vec<PasswordsWithPolicy> {
+// The character occurs between `min` and `max` times.
+struct CountRangePolicy;
+
+impl PasswordPolicy for CountRangePolicy {
+    fn name(&self) -> &'static str {
+        "count-range"
+    }
+
+    fn is_valid(&self, password: &PasswordsWithPolicy) -> bool {
+        (password.min..=password.max).contains(&password.char_count())
+    }
+}
+
+// The character is at exactly one of the (1-based) positions `min` and `max`.
+struct ExactlyOnePositionPolicy;
+
+impl PasswordPolicy for ExactlyOnePositionPolicy {
+    fn name(&self) -> &'static str {
+        "exactly-one-position"
+    }
+
+    fn is_valid(&self, password: &PasswordsWithPolicy) -> bool {
+        password.position_matches() == Some(1)
+    }
+}
+
+struct AtLeastOnePositionPolicy;
+
+impl PasswordPolicy for AtLeastOnePositionPolicy {
+    fn name(&self) -> &'static str {
+        "at-least-one-position"
+    }
+
+    fn is_valid(&self, password: &PasswordsWithPolicy) -> bool {
+        password.position_matches().unwrap_or(0) >= 1
+    }
+}
+
+struct AllPositionsPolicy;
+
+impl PasswordPolicy for AllPositionsPolicy {
+    fn name(&self) -> &'static str {
+        "all-positions"
+    }
+
+    fn is_valid(&self, password: &PasswordsWithPolicy) -> bool {
+        password.position_matches() == Some(2)
+    }
+}
+
+// The character does not occur in the password at all.
+struct ForbiddenCharPolicy;
+
+impl PasswordPolicy for ForbiddenCharPolicy {
+    fn name(&self) -> &'static str {
+        "forbidden-char"
+    }
+
+    fn is_valid(&self, password: &PasswordsWithPolicy) -> bool {
+        password.char_count() == 0
+    }
+}
+
+fn builtin_policies() -> Vec<Box<dyn PasswordPolicy>> {
+    vec![
+        Box::new(CountRangePolicy),
+        Box::new(ExactlyOnePositionPolicy),
+        Box::new(AtLeastOnePositionPolicy),
+        Box::new(AllPositionsPolicy),
+        Box::new(ForbiddenCharPolicy),
+    ]
+}
+
+fn policy_by_name(name: &str) -> Option<Box<dyn PasswordPolicy>> {
+    builtin_policies().into_iter().find(|p| p.name() == name)
+}
+
+#[derive(Debug, PartialEq)]
+struct PolicyReportLine {
+    line: usize,
+    passed: Vec<&'static str>,
+}
+
+impl fmt::Display for PolicyReportLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.passed.is_empty() {
+            write!(f, "line {}: -", self.line)
+        } else {
+            write!(f, "line {}: {}", self.line, self.passed.join(", "))
+        }
+    }
+}
+
+fn policy_report(
+    input: &[PasswordsWithPolicy],
+    policies: &[Box<dyn PasswordPolicy>],
+) -> Vec<PolicyReportLine> {
     input
-        .lines()
-        .map(|a| parse_pw_line(a))
-        .collect::<Vec<PasswordsWithPolicy>>()
+        .iter()
+        .enumerate()
+        .map(|(i, password)| PolicyReportLine {
+            line: i + 1,
+            passed: policies
+                .iter()
+                .filter(|p| p.is_valid(password))
+                .map(|p| p.name())
+                .collect(),
+        })
+        .collect()
 }
 
-#[aoc(day2, part1)]
-fn part1(input: &[PasswordsWithPolicy]) -> i32 {
-    let mut valid_pw_count = 0;
-    for password in input {
-        let mut char_count = 0;
-        for char in password.pw.chars() {
-            if char == password.char {
-                char_count += 1
+fn count_valid(input: &[PasswordsWithPolicy], policy: &dyn PasswordPolicy) -> usize {
+    input.iter().filter(|p| policy.is_valid(p)).count()
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    line: usize,
+    column: usize,
+    expected: &'static str,
+    found: Option<char>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected {}, found ",
+            self.line, self.column, self.expected
+        )?;
+        match self.found {
+            Some(c) => write!(f, "{:?}", c),
+            None => write!(f, "end of line"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Reads one `<min>-<max> <char>: <password>` line, keeping track of the column for errors.
+struct LineParser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl LineParser {
+    fn new(input: &str, line: usize) -> Self {
+        LineParser {
+            chars: input.chars().collect(),
+            pos: 0,
+            line,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error_at(&self, pos: usize, expected: &'static str) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: pos + 1,
+            expected,
+            found: self.chars.get(pos).copied(),
+        }
+    }
+
+    fn expect(&mut self, c: char, expected: &'static str) -> Result<(), ParseError> {
+        if self.peek() != Some(c) {
+            return Err(self.error_at(self.pos, expected));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn number(&mut self) -> Result<i32, ParseError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse::<i32>()
+            .map_err(|_| self.error_at(start, "a number"))
+    }
+
+    fn letter(&mut self) -> Result<char, ParseError> {
+        match self.peek() {
+            Some(c) if !c.is_whitespace() && c != ':' => {
+                self.pos += 1;
+                Ok(c)
             }
+            _ => Err(self.error_at(self.pos, "a policy character")),
+        }
+    }
+
+    fn password(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| !c.is_whitespace()) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error_at(start, "a password"));
         }
-        if char_count >= password.min && char_count <= password.max {
-            valid_pw_count += 1;
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn end(&self) -> Result<(), ParseError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error_at(self.pos, "end of line")),
         }
     }
-    return valid_pw_count;
 }
 
-#[aoc(day2, part2)]
-fn part2(input: &[PasswordsWithPolicy]) -> i32 {
-    let mut valid_pw_count = 0;
-    for password in input {
-        let mut occurrences = 0;
-        for pos in &[password.min, password.max] {
-            if password.pw.chars().nth((*pos - 1) as usize).unwrap() == password.char {
-                occurrences += 1
+fn parse_pw_line(input: &str, line: usize) -> Result<PasswordsWithPolicy, ParseError> {
+    let mut parser = LineParser::new(input, line);
+    let min = parser.number()?;
+    parser.expect('-', "'-'")?;
+    let max = parser.number()?;
+    parser.expect(' ', "' '")?;
+    let char = parser.letter()?;
+    parser.expect(':', "':'")?;
+    parser.expect(' ', "' '")?;
+    let pw = parser.password()?;
+    parser.end()?;
+    Ok(PasswordsWithPolicy { min, max, char, pw })
+}
+
+#[aoc_generator(day2)]
+fn generator_input(input: &str) -> Result<Vec<PasswordsWithPolicy>, ParseError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, a)| parse_pw_line(a, i + 1))
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+struct LenientParse {
+    passwords: Vec<PasswordsWithPolicy>,
+    // The 1-based source line of each password.
+    lines: Vec<usize>,
+    skipped: Vec<ParseError>,
+}
+
+// Like `generator_input`, but malformed lines are skipped and collected instead of aborting.
+fn generator_input_lenient(input: &str) -> LenientParse {
+    let mut passwords = vec![];
+    let mut lines = vec![];
+    let mut skipped = vec![];
+    for (i, line) in input.lines().enumerate() {
+        match parse_pw_line(line, i + 1) {
+            Ok(password) => {
+                passwords.push(password);
+                lines.push(i + 1);
             }
-        }
-        if occurrences == 1 {
-            valid_pw_count += 1
+            Err(e) => skipped.push(e),
         }
     }
-    return valid_pw_count;
+    LenientParse {
+        passwords,
+        lines,
+        skipped,
+    }
+}
+
+#[aoc(day2, part1)]
+fn part1(input: &[PasswordsWithPolicy]) -> usize {
+    count_valid(input, &CountRangePolicy)
+}
+
+#[aoc(day2, part2)]
+fn part2(input: &[PasswordsWithPolicy]) -> usize {
+    count_valid(input, &ExactlyOnePositionPolicy)
 }
 
 #[cfg(test)]
 pub mod tests {
-    use super::{generator_input, part1, part2, PasswordsWithPolicy};
+    use super::{
+        builtin_policies, generator_input, generator_input_lenient, parse_pw_line, part1, part2,
+        policy_by_name, policy_report, ParseError, PasswordsWithPolicy,
+    };
 
     #[test]
     fn generator() {
         let input_raw = "1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc";
-        let input = generator_input(&input_raw);
+        let input = generator_input(&input_raw).unwrap();
         let first_line = PasswordsWithPolicy {
             min: 1,
             max: 3,
@@ -87,14 +347,112 @@ pub mod tests {
     #[test]
     fn day2_part1() {
         let input_raw = "1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc";
-        let input = generator_input(&input_raw);
+        let input = generator_input(&input_raw).unwrap();
         assert_eq!(part1(&input), 2);
     }
 
     #[test]
     fn day2_part2() {
         let input_raw = "1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc";
-        let input = generator_input(&input_raw);
+        let input = generator_input(&input_raw).unwrap();
         assert_eq!(part2(&input), 1);
     }
+
+    #[test]
+    fn select_policy_by_name() {
+        let input = generator_input("1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc").unwrap();
+        let expected = [
+            ("count-range", [true, false, true]),
+            ("exactly-one-position", [true, false, false]),
+            ("at-least-one-position", [true, false, true]),
+            ("all-positions", [false, false, true]),
+            ("forbidden-char", [false, true, false]),
+        ];
+        for (name, valid) in expected.iter() {
+            let policy = policy_by_name(name).unwrap();
+            assert_eq!(policy.name(), *name);
+            for (password, &valid) in input.iter().zip(valid.iter()) {
+                assert_eq!(policy.is_valid(password), valid);
+            }
+        }
+        assert!(policy_by_name("no-such-policy").is_none());
+    }
+
+    #[test]
+    fn report_lists_passed_policies() {
+        let input = generator_input("1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc").unwrap();
+        let report = policy_report(&input, &builtin_policies());
+        assert_eq!(report[0].line, 1);
+        assert_eq!(
+            report[0].passed,
+            vec![
+                "count-range",
+                "exactly-one-position",
+                "at-least-one-position"
+            ]
+        );
+        assert_eq!(report[1].to_string(), "line 2: forbidden-char");
+        assert_eq!(
+            report[2].to_string(),
+            "line 3: count-range, at-least-one-position, all-positions"
+        );
+    }
+
+    #[test]
+    fn parse_errors_report_position() {
+        let cases = [
+            ("x-3 a: abc", 1, "a number", Some('x')),
+            ("1+3 a: abc", 2, "'-'", Some('+')),
+            ("1-3a: abc", 4, "' '", Some('a')),
+            ("1-3 : abc", 5, "a policy character", Some(':')),
+            ("1-3 ab: abc", 6, "':'", Some('b')),
+            ("1-3 a:", 7, "' '", None),
+            ("1-3 a: ", 8, "a password", None),
+            ("1-3 a: abc def", 11, "end of line", Some(' ')),
+            ("99999999999-3 a: abc", 1, "a number", Some('9')),
+        ];
+        for (line, column, expected, found) in cases.iter() {
+            assert_eq!(
+                parse_pw_line(line, 4),
+                Err(ParseError {
+                    line: 4,
+                    column: *column,
+                    expected,
+                    found: *found,
+                })
+            );
+        }
+        let err = generator_input("1-3 a: abcde\n1-3 b cdefg").unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 6: expected ':', found ' '");
+    }
+
+    #[test]
+    fn lenient_parse_collects_bad_lines() {
+        let parsed = generator_input_lenient("1-3 a: abcde\n1-3 b cdefg\n\n2-9 c: ccccccccc");
+        assert_eq!(parsed.passwords.len(), 2);
+        assert_eq!(parsed.passwords[1].pw, "ccccccccc");
+        assert_eq!(parsed.lines, vec![1, 4]);
+        assert_eq!(
+            parsed.skipped.iter().map(|e| e.line).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(
+            parsed.skipped[1].to_string(),
+            "line 3, column 1: expected a number, found end of line"
+        );
+    }
+
+    #[test]
+    fn position_policies_reject_out_of_range() {
+        let input = generator_input("1-6 a: abcde\n0-2 b: bbcde\n2-3 c: cc").unwrap();
+        for name in &[
+            "exactly-one-position",
+            "at-least-one-position",
+            "all-positions",
+        ] {
+            let policy = policy_by_name(name).unwrap();
+            assert!(input.iter().all(|p| !policy.is_valid(p)), "{}", name);
+        }
+        assert_eq!(part2(&input), 0);
+    }
 }
@@ -0,0 +1,84 @@
+use super::{
+    builtin_policies, generator_input, generator_input_lenient, policy_by_name, policy_report,
+    PasswordPolicy,
+};
+
+pub const USAGE: &str = "usage: passwords [--input FILE] [--lenient] [POLICY...]
+Lists the policies each password passes, out of POLICY or all of them by default:
+count-range, exactly-one-position, at-least-one-position, all-positions, forbidden-char.
+With --lenient, malformed lines are listed at the end instead of stopping at the first one.";
+
+// Checks the passwords in `input` and returns what it prints, or the usage problem or first parse
+// error as a message. `args` does not include the program name or the input file option.
+pub fn run(args: &[String], input: &str) -> Result<String, String> {
+    let mut lenient = false;
+    let mut policies: Vec<Box<dyn PasswordPolicy>> = vec![];
+    for arg in args {
+        match arg.as_str() {
+            "--lenient" => lenient = true,
+            name => policies.push(
+                policy_by_name(name)
+                    .ok_or_else(|| format!("unknown policy '{}'\n{}", name, USAGE))?,
+            ),
+        }
+    }
+    if policies.is_empty() {
+        policies = builtin_policies();
+    }
+    let (passwords, lines, skipped) = if lenient {
+        let parsed = generator_input_lenient(input);
+        (parsed.passwords, parsed.lines, parsed.skipped)
+    } else {
+        let passwords = generator_input(input).map_err(|e| e.to_string())?;
+        let lines = (1..=passwords.len()).collect();
+        (passwords, lines, vec![])
+    };
+    let mut output = String::new();
+    for (mut report, line) in policy_report(&passwords, &policies).into_iter().zip(lines) {
+        report.line = line;
+        output.push_str(&format!("{}\n", report));
+    }
+    for error in skipped {
+        output.push_str(&format!("skipped {}\n", error));
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::run;
+
+    fn run_args(args: &[&str], input: &str) -> Result<String, String> {
+        let args = args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        run(&args, input)
+    }
+
+    #[test]
+    fn reports_policies() {
+        let input = "1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc";
+        assert_eq!(
+            run_args(&["count-range", "forbidden-char"], input).unwrap(),
+            "line 1: count-range\nline 2: forbidden-char\nline 3: count-range\n"
+        );
+        assert!(run_args(&[], input)
+            .unwrap()
+            .starts_with("line 1: count-range, exactly-one-position, at-least-one-position\n"));
+        assert!(run_args(&["no-such-policy"], input)
+            .unwrap_err()
+            .starts_with("unknown policy 'no-such-policy'\nusage:"));
+    }
+
+    #[test]
+    fn lenient_keeps_source_lines() {
+        let input = "1-3 a: abcde\n1-3 b cdefg\n2-9 c: ccccccccc";
+        assert_eq!(
+            run_args(&["all-positions"], input).unwrap_err(),
+            "line 2, column 6: expected ':', found ' '"
+        );
+        assert_eq!(
+            run_args(&["--lenient", "all-positions"], input).unwrap(),
+            "line 1: -\nline 3: all-positions\n\
+             skipped line 2, column 6: expected ':', found ' '\n"
+        );
+    }
+}
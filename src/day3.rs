@@ -1,51 +1,53 @@
+use crate::grid::{Grid, GridError, Topology};
+
+pub mod slopes;
+
 #[derive(Debug, PartialEq)]
-enum TobogganField {
+pub enum TobogganField {
     Open,
     Tree,
 }
 
 impl TobogganField {
-    fn new(char: char) -> Self {
+    fn new(char: char) -> Option<Self> {
         match char {
-            '.' => TobogganField::Open,
-            '#' => TobogganField::Tree,
-            _ => panic!("This can't be! :O"),
+            '.' => Some(TobogganField::Open),
+            '#' => Some(TobogganField::Tree),
+            _ => None,
         }
     }
-}
 
-struct TobogganMap {
-    width: usize,
-    height: usize,
-    fields: Vec<TobogganField>,
+    fn to_char(&self) -> char {
+        match self {
+            TobogganField::Open => '.',
+            TobogganField::Tree => '#',
+        }
+    }
 }
 
+pub type TobogganMap = Grid<TobogganField>;
+
 #[aoc_generator(day3)]
-fn generator_input(input: &str) -> TobogganMap {
-    let width = input.lines().next().unwrap().len();
-    let height = input.lines().count();
-    let fields = input
-        .lines()
-        .map(|a| a.chars())
-        .flat_map(|c| c.map(|f| TobogganField::new(f)))
-        .collect::<Vec<TobogganField>>();
-    TobogganMap {
-        width,
-        height,
-        fields,
-    }
+fn generator_input(input: &str) -> Result<TobogganMap, GridError> {
+    Grid::parse(input, Topology::Cylinder, TobogganField::new)
+}
+
+// Positions visited on the way down, with x left unwrapped so paths can be drawn.
+fn slope_path(
+    map: &TobogganMap,
+    step_size_x: usize,
+    step_size_y: usize,
+) -> impl Iterator<Item = (usize, usize)> {
+    (0..map.height())
+        .step_by(step_size_y)
+        .enumerate()
+        .map(move |(step, y)| (step * step_size_x, y))
 }
 
 fn count_trees_in_slope(map: &TobogganMap, step_size_x: usize, step_size_y: usize) -> i32 {
-    let mut x = 0;
-    let mut tree_count = 0;
-    for y in (0..map.height).step_by(step_size_y) {
-        if map.fields[y * map.width + x] == TobogganField::Tree {
-            tree_count += 1;
-        }
-        x = (x + step_size_x) % map.width;
-    }
-    return tree_count;
+    slope_path(map, step_size_x, step_size_y)
+        .filter(|&(x, y)| map.get(x as i64, y as i64) == Some(&TobogganField::Tree))
+        .count() as i32
 }
 
 #[aoc(day3, part1)]
@@ -65,31 +67,32 @@ fn part2(input: &TobogganMap) -> i32 {
 #[cfg(test)]
 pub mod tests {
     use super::{generator_input, part1, part2, TobogganField};
-    static INPUT_RAW: &str = "..##.......\n#...#...#..\n.#....#..#.\n..#.#...#.#\n.#...##..#.\n..#.##.....\n.#.#.#....#\n.#........#\n#.##...#...\n#...##....#\n.#..#...#.#\n";
+    pub static INPUT_RAW: &str = "..##.......\n#...#...#..\n.#....#..#.\n..#.#...#.#\n.#...##..#.\n..#.##.....\n.#.#.#....#\n.#........#\n#.##...#...\n#...##....#\n.#..#...#.#\n";
 
     #[test]
     fn generator() {
-        let map = generator_input(&INPUT_RAW);
-        assert_eq!(map.width, 11);
-        assert_eq!(map.height, 11);
-        assert_eq!(map.fields[0], TobogganField::Open);
-        assert_eq!(map.fields[1], TobogganField::Open);
-        assert_eq!(map.fields[2], TobogganField::Tree);
-        assert_eq!(map.fields[3], TobogganField::Tree);
-        assert_eq!(map.fields[4], TobogganField::Open);
-        assert_eq!(map.fields[11], TobogganField::Tree);
-        assert_eq!(map.fields[12], TobogganField::Open);
+        let map = generator_input(&INPUT_RAW).unwrap();
+        assert_eq!(map.width(), 11);
+        assert_eq!(map.height(), 11);
+        assert_eq!(map.get(0, 0), Some(&TobogganField::Open));
+        assert_eq!(map.get(1, 0), Some(&TobogganField::Open));
+        assert_eq!(map.get(2, 0), Some(&TobogganField::Tree));
+        assert_eq!(map.get(3, 0), Some(&TobogganField::Tree));
+        assert_eq!(map.get(4, 0), Some(&TobogganField::Open));
+        assert_eq!(map.get(0, 1), Some(&TobogganField::Tree));
+        assert_eq!(map.get(1, 1), Some(&TobogganField::Open));
+        assert_eq!(map.get(13, 0), Some(&TobogganField::Tree));
     }
 
     #[test]
     fn day3_part1() {
-        let map = generator_input(&INPUT_RAW);
+        let map = generator_input(&INPUT_RAW).unwrap();
         assert_eq!(part1(&map), 7);
     }
 
     #[test]
     fn day3_part2() {
-        let map = generator_input(&INPUT_RAW);
+        let map = generator_input(&INPUT_RAW).unwrap();
         assert_eq!(part2(&map), 336);
     }
 }
@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+
+use super::{count_trees_in_slope, slope_path, TobogganField, TobogganMap};
+use crate::grid::GridError;
+
+pub fn parse(input: &str) -> Result<TobogganMap, GridError> {
+    super::generator_input(input)
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SlopeExtremes {
+    pub fewest_trees: i32,
+    pub fewest: Vec<(usize, usize)>,
+    pub most_trees: i32,
+    pub most: Vec<(usize, usize)>,
+}
+
+// Tries every slope with 0 <= dx <= max_dx and 1 <= dy <= max_dy. Ties are all kept, ordered by
+// (dx, dy).
+pub fn search_slopes(map: &TobogganMap, max_dx: usize, max_dy: usize) -> Option<SlopeExtremes> {
+    let mut extremes: Option<SlopeExtremes> = None;
+    for dx in 0..=max_dx {
+        for dy in 1..=max_dy {
+            let trees = count_trees_in_slope(map, dx, dy);
+            let e = extremes.get_or_insert(SlopeExtremes {
+                fewest_trees: trees,
+                fewest: vec![],
+                most_trees: trees,
+                most: vec![],
+            });
+            if trees < e.fewest_trees {
+                e.fewest_trees = trees;
+                e.fewest.clear();
+            }
+            if trees == e.fewest_trees {
+                e.fewest.push((dx, dy));
+            }
+            if trees > e.most_trees {
+                e.most_trees = trees;
+                e.most.clear();
+            }
+            if trees == e.most_trees {
+                e.most.push((dx, dy));
+            }
+        }
+    }
+    extremes
+}
+
+// Draws the map like the puzzle text does, repeated to the right as far as the path goes, with
+// visited open squares marked `O` and hit trees marked `X`.
+pub fn render_path(map: &TobogganMap, step_size_x: usize, step_size_y: usize) -> String {
+    let path = slope_path(map, step_size_x, step_size_y).collect::<HashSet<_>>();
+    let max_x = path.iter().map(|&(x, _)| x).max().unwrap_or(0);
+    let width = (max_x / map.width() + 1) * map.width();
+    (0..map.height())
+        .map(|y| {
+            (0..width)
+                .map(|x| {
+                    let field = map.get(x as i64, y as i64).unwrap();
+                    match (path.contains(&(x, y)), field) {
+                        (true, TobogganField::Open) => 'O',
+                        (true, TobogganField::Tree) => 'X',
+                        (false, field) => field.to_char(),
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{parse, render_path, search_slopes};
+    use crate::day3::tests::INPUT_RAW;
+
+    #[test]
+    fn slope_search() {
+        let map = parse(INPUT_RAW).unwrap();
+        let extremes = search_slopes(&map, 7, 2).unwrap();
+        assert_eq!(extremes.fewest_trees, 0);
+        assert_eq!(extremes.fewest, vec![(5, 2)]);
+        assert_eq!(extremes.most_trees, 7);
+        assert_eq!(extremes.most, vec![(3, 1)]);
+        let extremes = search_slopes(&map, 2, 2).unwrap();
+        assert_eq!(extremes.fewest_trees, 1);
+        assert_eq!(extremes.fewest, vec![(0, 2), (2, 1), (2, 2)]);
+        assert_eq!(extremes.most_trees, 3);
+        assert_eq!(extremes.most, vec![(0, 1)]);
+        assert_eq!(search_slopes(&map, 3, 0), None);
+    }
+
+    #[test]
+    fn path_rendering() {
+        let map = parse(INPUT_RAW).unwrap();
+        let expected = r#"O.##.........##.........##.......
+#..O#...#..#...#...#..#...#...#..
+.#....X..#..#....#..#..#....#..#.
+..#.#...#O#..#.#...#.#..#.#...#.#
+.#...##..#..X...##..#..#...##..#.
+..#.##.......#.X#.......#.##.....
+.#.#.#....#.#.#.#.O..#.#.#.#....#
+.#........#.#........X.#........#
+#.##...#...#.##...#...#.X#...#...
+#...##....##...##....##...#X....#
+.#..#...#.#.#..#...#.#.#..#...X.#"#;
+        assert_eq!(render_path(&map, 3, 1), expected);
+    }
+}
@@ -1,7 +1,16 @@
+use std::convert::TryFrom;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
-struct Passport {
+use crate::records::{DuplicateKeys, Record, RecordError, RecordParser};
+use validated::ValidatedPassport;
+
+pub mod report;
+mod schema;
+mod validated;
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Passport {
     byr: Option<String>,
     iyr: Option<String>,
     eyr: Option<String>,
@@ -13,15 +22,30 @@ struct Passport {
 }
 
 impl Passport {
-    fn from_str(input: String) -> Self {
-        // let t = input.split(",").collect::<Vec<_>>();
-        let reformatted = input
-            .split(" ")
-            .map(|s| s.split(":").collect::<Vec<_>>())
-            .map(|f| format!("{}=\"{}\"", f[0], f[1]))
-            .collect::<Vec<_>>()
-            .join("\n");
-        toml::from_str(&reformatted[..]).unwrap()
+    const FIELDS: [&'static str; 8] = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid", "cid"];
+
+    fn from_record(record: &Record) -> Self {
+        let field = |key| record.get(key).map(|v| v.to_string());
+        Passport {
+            byr: field("byr"),
+            iyr: field("iyr"),
+            eyr: field("eyr"),
+            hgt: field("hgt"),
+            hcl: field("hcl"),
+            ecl: field("ecl"),
+            pid: field("pid"),
+            cid: field("cid"),
+        }
+    }
+
+    #[cfg(test)]
+    fn from_str(input: &str) -> Result<Self, RecordError> {
+        let parsed = RecordParser::new(DuplicateKeys::Error).parse(input)?;
+        Ok(parsed
+            .records
+            .first()
+            .map(Passport::from_record)
+            .unwrap_or_default())
     }
 
     fn is_valid(&self) -> bool {
@@ -34,120 +58,34 @@ impl Passport {
             && self.pid.is_some()
     }
 
-    fn is_valid_strict(&self) -> bool {
-        self.is_byr_valid()
-            && self.is_iyr_valid()
-            && self.is_eyr_valid()
-            && self.is_hgt_valid()
-            && self.is_hcl_valid()
-            && self.is_ecl_valid()
-            && self.is_pid_valid()
-    }
-
-    fn is_byr_valid(&self) -> bool {
-        match self.byr.clone() {
-            Some(byr) => {
-                byr.len() == 4
-                    && match byr.parse::<i32>() {
-                        Ok(year) => 1920 <= year && year <= 2002,
-                        Err(_) => false,
-                    }
-            }
-            None => false,
-        }
-    }
-
-    fn is_iyr_valid(&self) -> bool {
-        match self.iyr.clone() {
-            Some(iyr) => {
-                iyr.len() == 4
-                    && match iyr.parse::<i32>() {
-                        Ok(year) => 2010 <= year && year <= 2020,
-                        Err(_) => false,
-                    }
-            }
-            None => false,
-        }
-    }
-
-    fn is_eyr_valid(&self) -> bool {
-        match self.eyr.clone() {
-            Some(eyr) => {
-                eyr.len() == 4
-                    && match eyr.parse::<i32>() {
-                        Ok(year) => 2020 <= year && year <= 2030,
-                        Err(_) => false,
-                    }
-            }
-            None => false,
-        }
-    }
-
-    fn is_hgt_valid(&self) -> bool {
-        match self.hgt.clone() {
-            Some(eyr) => {
-                eyr.ends_with("cm")
-                    && match eyr[..eyr.len() - 2].parse::<i32>() {
-                        Ok(height) => 150 <= height && height <= 193,
-                        Err(_) => false,
-                    }
-                    || eyr.ends_with("in")
-                        && match eyr[..eyr.len() - 2].parse::<i32>() {
-                            Ok(height) => 59 <= height && height <= 76,
-                            Err(_) => false,
-                        }
-            }
-            None => false,
-        }
-    }
-
-    fn is_hcl_valid(&self) -> bool {
-        match self.hcl.clone() {
-            Some(hcl) => {
-                hcl.starts_with("#")
-                    && (hcl[1..].chars().all(|c| {
-                        (0..=9)
-                            .map(|n| n.to_string())
-                            .collect::<Vec<_>>()
-                            .contains(&c.to_string())
-                    } || ('a'..='f').contains(&c)))
-            }
-            None => false,
-        }
-    }
-
-    fn is_ecl_valid(&self) -> bool {
-        match self.ecl.clone() {
-            Some(ecl) => match ecl.as_str() {
-                "amb" => true,
-                "blu" => true,
-                "brn" => true,
-                "gry" => true,
-                "grn" => true,
-                "hzl" => true,
-                "oth" => true,
-                _ => false,
-            },
-            None => false,
-        }
+    fn get(&self, field: &str) -> Option<&str> {
+        let value = match field {
+            "byr" => &self.byr,
+            "iyr" => &self.iyr,
+            "eyr" => &self.eyr,
+            "hgt" => &self.hgt,
+            "hcl" => &self.hcl,
+            "ecl" => &self.ecl,
+            "pid" => &self.pid,
+            "cid" => &self.cid,
+            _ => return None,
+        };
+        value.as_deref()
     }
+}
 
-    fn is_pid_valid(&self) -> bool {
-        match self.pid.clone() {
-            Some(pid) => pid.len() == 9 && pid.chars().all(|c| ('0'..='9').contains(&c)),
-            None => false,
-        }
-    }
+fn parse_passports(input: &str, duplicates: DuplicateKeys) -> Result<Vec<Passport>, RecordError> {
+    let parsed = RecordParser::new(duplicates)
+        .known_keys(&Passport::FIELDS)
+        .parse(input)?;
+    // Keys outside `Passport::FIELDS` are collected in `parsed.unknown`. Neither part cares about
+    // extra fields, so they are ignored rather than rejected.
+    Ok(parsed.records.iter().map(Passport::from_record).collect())
 }
 
 #[aoc_generator(day4)]
-fn generator_input(input: &str) -> Vec<Passport> {
-    input
-        .split("\n\n")
-        .map(|s| s.replace("\n", " "))
-        .map(|s| Passport::from_str(s))
-        // .map(|f| toml::from_str::<Passport>(&f[..]).unwrap())
-        .collect::<Vec<_>>()
+fn generator_input(input: &str) -> Result<Vec<Passport>, RecordError> {
+    parse_passports(input, DuplicateKeys::Error)
 }
 
 #[aoc(day4, part1)]
@@ -157,12 +95,17 @@ fn part1(input: &[Passport]) -> usize {
 
 #[aoc(day4, part2)]
 fn part2(input: &[Passport]) -> usize {
-    input.iter().filter(|p| p.is_valid_strict()).count()
+    input
+        .iter()
+        .filter(|&p| ValidatedPassport::try_from(p).is_ok())
+        .count()
 }
 
 #[cfg(test)]
 pub mod tests {
-    use super::{generator_input, part1, part2, Passport};
+    use std::convert::TryFrom;
+
+    use super::{generator_input, part1, part2, Passport, ValidatedPassport};
     static INPUT_RAW: &str = r#"ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
 byr:1937 iyr:2017 cid:147 hgt:183cm
 
@@ -179,7 +122,7 @@ iyr:2011 ecl:brn hgt:59in"#;
 
     #[test]
     fn generator() {
-        let input = generator_input(&INPUT_RAW);
+        let input = generator_input(&INPUT_RAW).unwrap();
         assert_eq!(input.len(), 4);
     }
 
@@ -192,8 +135,8 @@ iyr:2011 ecl:brn hgt:59in"#;
             "iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719",
         ];
         for valid in valids {
-            let passport = Passport::from_str(valid.to_string());
-            assert!(passport.is_valid_strict());
+            let passport = Passport::from_str(valid).unwrap();
+            assert!(ValidatedPassport::try_from(&passport).is_ok());
         }
     }
 
@@ -206,20 +149,38 @@ iyr:2011 ecl:brn hgt:59in"#;
             "hgt:59cm ecl:zzz eyr:2038 hcl:74454a iyr:2023 pid:3556412378 byr:2007",
         ];
         for invalid in invalids {
-            let passport = Passport::from_str(invalid.to_string());
-            assert!(!passport.is_valid_strict());
+            let passport = Passport::from_str(invalid).unwrap();
+            assert!(ValidatedPassport::try_from(&passport).is_err());
         }
     }
 
     #[test]
     fn day3_part1() {
-        let input = generator_input(&INPUT_RAW);
+        let input = generator_input(&INPUT_RAW).unwrap();
         assert_eq!(part1(&input), 2);
     }
 
     #[test]
     fn day3_part2() {
-        let input = generator_input(&INPUT_RAW);
+        let input = generator_input(&INPUT_RAW).unwrap();
         assert_eq!(part2(&input), 2);
     }
+
+    #[test]
+    fn generator_keeps_values_verbatim() {
+        let input = generator_input("hcl:\"#123abc\" pid:1:2\\3\n\necl:brn").unwrap();
+        assert_eq!(input.len(), 2);
+        assert_eq!(input[0].hcl.as_deref(), Some("\"#123abc\""));
+        assert_eq!(input[0].pid.as_deref(), Some("1:2\\3"));
+        assert_eq!(input[1].ecl.as_deref(), Some("brn"));
+    }
+
+    #[test]
+    fn generator_rejects_duplicate_keys() {
+        let err = generator_input("ecl:brn\n\nbyr:1990\npid:1 byr:1991").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 4, column 7: duplicate key 'byr', first defined at line 3, column 1"
+        );
+    }
 }
@@ -0,0 +1,427 @@
+use serde::Serialize;
+
+use super::parse_passports;
+use super::schema::Failure;
+pub use super::schema::Schema;
+pub use super::Passport;
+use crate::records::DuplicateKeys;
+
+pub const USAGE: &str =
+    "usage: passports [--input FILE] [--csv | --json] [--duplicates error|first|last]
+Checks every passport against the default schema and prints each field's value, status and failure
+reason per passport, followed by how often each field failed. CSV by default. A key repeated within
+a passport is an error unless --duplicates says to keep its first or last value.";
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldStatus {
+    Valid,
+    Missing,
+    Invalid,
+}
+
+impl FieldStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            FieldStatus::Valid => "valid",
+            FieldStatus::Missing => "missing",
+            FieldStatus::Invalid => "invalid",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct FieldReport {
+    pub field: String,
+    pub value: Option<String>,
+    pub status: FieldStatus,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct PassportRow {
+    pub index: usize,
+    pub valid: bool,
+    pub fields: Vec<FieldReport>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct FieldSummary {
+    pub field: String,
+    pub missing: usize,
+    pub invalid: usize,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Summary {
+    pub passports: usize,
+    pub valid: usize,
+    pub fields: Vec<FieldSummary>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ValidationReport {
+    pub passports: Vec<PassportRow>,
+    pub summary: Summary,
+}
+
+// Validates every passport against the schema. Fields appear in schema order, passports keep
+// their (0-based) position in the batch.
+pub fn build_report(passports: &[Passport], schema: &Schema) -> ValidationReport {
+    let mut fields = schema
+        .fields
+        .iter()
+        .map(|field| FieldSummary {
+            field: field.name.clone(),
+            missing: 0,
+            invalid: 0,
+        })
+        .collect::<Vec<_>>();
+
+    let rows = passports
+        .iter()
+        .enumerate()
+        .map(|(index, passport)| {
+            let failures = schema.validate(passport);
+            let reports = schema
+                .fields
+                .iter()
+                .zip(fields.iter_mut())
+                .map(|(field, summary)| {
+                    let failure = failures.iter().find(|f| f.field == field.name);
+                    let status = match failure.map(|f| &f.reason) {
+                        None => FieldStatus::Valid,
+                        Some(Failure::Missing) => FieldStatus::Missing,
+                        Some(_) => FieldStatus::Invalid,
+                    };
+                    match status {
+                        FieldStatus::Missing => summary.missing += 1,
+                        FieldStatus::Invalid => summary.invalid += 1,
+                        FieldStatus::Valid => {}
+                    }
+                    FieldReport {
+                        field: field.name.clone(),
+                        value: passport.get(&field.name).map(|v| v.to_string()),
+                        status,
+                        reason: failure.map(|f| f.reason.to_string()),
+                    }
+                })
+                .collect();
+            PassportRow {
+                index,
+                valid: failures.is_empty(),
+                fields: reports,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    ValidationReport {
+        summary: Summary {
+            passports: rows.len(),
+            valid: rows.iter().filter(|row| row.valid).count(),
+            fields,
+        },
+        passports: rows,
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn json_option(value: &Option<String>) -> String {
+    value.as_deref().map_or("null".to_string(), json_string)
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_line(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|v| csv_escape(v))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl ValidationReport {
+    // One passport and one field summary per line.
+    pub fn to_json(&self) -> String {
+        let passports = self
+            .passports
+            .iter()
+            .map(|row| {
+                let fields = row
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        format!(
+                            "{{\"field\": {}, \"value\": {}, \"status\": \"{}\", \"reason\": {}}}",
+                            json_string(&field.field),
+                            json_option(&field.value),
+                            field.status.as_str(),
+                            json_option(&field.reason)
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                format!(
+                    "    {{\"index\": {}, \"valid\": {}, \"fields\": [{}]}}",
+                    row.index,
+                    row.valid,
+                    fields.join(", ")
+                )
+            })
+            .collect::<Vec<_>>();
+        let fields = self
+            .summary
+            .fields
+            .iter()
+            .map(|field| {
+                format!(
+                    "      {{\"field\": {}, \"missing\": {}, \"invalid\": {}}}",
+                    json_string(&field.field),
+                    field.missing,
+                    field.invalid
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "{{\n  \"passports\": [\n{}\n  ],\n  \"summary\": {{\n    \"passports\": {},\n    \
+             \"valid\": {},\n    \"fields\": [\n{}\n    ]\n  }}\n}}",
+            passports.join(",\n"),
+            self.summary.passports,
+            self.summary.valid,
+            fields.join(",\n")
+        )
+    }
+
+    // One row per passport with value, status and reason columns per field, followed by a blank
+    // line and the per-field failure summary.
+    pub fn to_csv(&self) -> String {
+        let names = self
+            .summary
+            .fields
+            .iter()
+            .map(|f| f.field.as_str())
+            .collect::<Vec<_>>();
+        let mut header = vec!["index".to_string(), "valid".to_string()];
+        for name in &names {
+            header.push(name.to_string());
+            header.push(format!("{}_status", name));
+            header.push(format!("{}_reason", name));
+        }
+        let mut lines = vec![csv_line(&header)];
+
+        for row in &self.passports {
+            let mut values = vec![row.index.to_string(), row.valid.to_string()];
+            for field in &row.fields {
+                values.push(field.value.clone().unwrap_or_default());
+                values.push(field.status.as_str().to_string());
+                values.push(field.reason.clone().unwrap_or_default());
+            }
+            lines.push(csv_line(&values));
+        }
+
+        lines.push(String::new());
+        lines.push("field,missing,invalid,failed".to_string());
+        for field in &self.summary.fields {
+            lines.push(csv_line(&[
+                field.field.clone(),
+                field.missing.to_string(),
+                field.invalid.to_string(),
+                (field.missing + field.invalid).to_string(),
+            ]));
+        }
+        lines.join("\n")
+    }
+}
+
+// Reports on the passports in `input` and returns what it prints, or the usage problem or parse
+// error as a message. `args` does not include the program name or the input file option.
+pub fn run(args: &[String], input: &str) -> Result<String, String> {
+    let mut json = false;
+    let mut duplicates = DuplicateKeys::Error;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--csv" => json = false,
+            "--json" => json = true,
+            "--duplicates" => {
+                duplicates = match args.next().map(|a| a.as_str()) {
+                    Some("error") => DuplicateKeys::Error,
+                    Some("first") => DuplicateKeys::KeepFirst,
+                    Some("last") => DuplicateKeys::KeepLast,
+                    _ => {
+                        return Err(format!(
+                            "--duplicates takes error, first or last\n{}",
+                            USAGE
+                        ))
+                    }
+                }
+            }
+            _ => return Err(format!("unknown argument '{}'\n{}", arg, USAGE)),
+        }
+    }
+    let passports = parse_passports(input, duplicates).map_err(|e| e.to_string())?;
+    let report = build_report(&passports, &Schema::default_schema());
+    let output = if json {
+        report.to_json()
+    } else {
+        report.to_csv()
+    };
+    Ok(output + "\n")
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{build_report, run, FieldStatus};
+    use crate::day4::generator_input;
+    use crate::day4::schema::Schema;
+
+    static INPUT_RAW: &str = r#"ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
+byr:1937 iyr:2017 cid:147 hgt:183cm
+
+iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
+hcl:#cfa07d byr:1929
+
+hgt:59cm ecl:zzz eyr:2038 hcl:"a,b" iyr:2023 pid:3556412378 byr:2007"#;
+
+    #[test]
+    fn report_rows_and_summary() {
+        let report = build_report(
+            &generator_input(INPUT_RAW).unwrap(),
+            &Schema::default_schema(),
+        );
+        assert_eq!(report.passports.len(), 3);
+        assert!(report.passports[0].valid);
+        assert!(!report.passports[1].valid);
+        let hgt = &report.passports[1].fields[3];
+        assert_eq!(hgt.field, "hgt");
+        assert_eq!(hgt.value, None);
+        assert_eq!(hgt.status, FieldStatus::Missing);
+        let byr = &report.passports[2].fields[0];
+        assert_eq!(byr.value.as_deref(), Some("2007"));
+        assert_eq!(byr.status, FieldStatus::Invalid);
+        assert_eq!(
+            byr.reason.as_deref(),
+            Some("2007 is not between 1920 and 2002")
+        );
+
+        assert_eq!(report.summary.passports, 3);
+        assert_eq!(report.summary.valid, 1);
+        let counts = report
+            .summary
+            .fields
+            .iter()
+            .map(|f| (f.field.as_str(), f.missing, f.invalid))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            vec![
+                ("byr", 0, 1),
+                ("iyr", 0, 1),
+                ("eyr", 0, 1),
+                ("hgt", 1, 1),
+                ("hcl", 0, 1),
+                ("ecl", 0, 1),
+                ("pid", 0, 1),
+                ("cid", 0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_export() {
+        let report = build_report(
+            &generator_input(INPUT_RAW).unwrap(),
+            &Schema::default_schema(),
+        );
+        let csv = report.to_csv();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1 + 3 + 1 + 1 + 8);
+        assert!(lines[0].starts_with("index,valid,byr,byr_status,byr_reason,iyr,"));
+        assert!(lines[1].starts_with("0,true,1937,valid,,2017,valid,,"));
+        assert!(lines[2].contains(",,missing,missing,"));
+        assert!(lines[3].contains(",\"\"\"a,b\"\"\",invalid,expected prefix '#',"));
+        assert_eq!(lines[4], "");
+        assert_eq!(lines[5], "field,missing,invalid,failed");
+        assert_eq!(lines[9], "hgt,1,1,2");
+    }
+
+    #[test]
+    fn json_export() {
+        let report = build_report(
+            &generator_input(INPUT_RAW).unwrap(),
+            &Schema::default_schema(),
+        );
+        let json = report.to_json();
+        assert!(json.starts_with("{\n  \"passports\": [\n    {\"index\": 0, \"valid\": "));
+        assert!(json.contains("\n    \"valid\": 1,\n"));
+        assert!(json.contains("{\"field\": \"hgt\", \"missing\": 1, \"invalid\": "));
+        assert!(json.contains(
+            "{\"field\": \"hgt\", \"value\": null, \"status\": \"missing\", \"reason\": \"missing\"}"
+        ));
+        assert!(json.contains(
+            "\"value\": \"\\\"a,b\\\"\", \"status\": \"invalid\", \"reason\": \"expected prefix '#'\"}"
+        ));
+        assert!(json.ends_with("\n    ]\n  }\n}"));
+    }
+
+    #[test]
+    fn run_formats() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        let csv = run(&args(&[]), INPUT_RAW).unwrap();
+        assert_eq!(
+            csv,
+            build_report(
+                &generator_input(INPUT_RAW).unwrap(),
+                &Schema::default_schema()
+            )
+            .to_csv()
+                + "\n"
+        );
+        assert!(run(&args(&["--json"]), INPUT_RAW)
+            .unwrap()
+            .starts_with("{\n"));
+        assert!(run(&args(&["--xml"]), INPUT_RAW)
+            .unwrap_err()
+            .starts_with("unknown argument '--xml'\nusage:"));
+    }
+
+    #[test]
+    fn run_duplicate_keys() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        let input =
+            "byr:1937 byr:2050 iyr:2017 eyr:2020 hgt:183cm hcl:#fffffd ecl:gry pid:860033327";
+        assert!(run(&args(&[]), input)
+            .unwrap_err()
+            .contains("duplicate key 'byr'"));
+        assert!(run(&args(&["--duplicates", "first"]), input)
+            .unwrap()
+            .contains("\n0,true,"));
+        assert!(run(&args(&["--duplicates", "last"]), input)
+            .unwrap()
+            .contains("\n0,false,"));
+        assert!(run(&args(&["--duplicates", "any"]), input)
+            .unwrap_err()
+            .starts_with("--duplicates takes error, first or last\nusage:"));
+    }
+}
@@ -0,0 +1,422 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Deserialize;
+
+use super::Passport;
+
+pub const DEFAULT_SCHEMA: &str = include_str!("default_schema.toml");
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: i64,
+    pub max: i64,
+}
+
+impl Bounds {
+    fn check(&self, value: i64) -> Result<(), Failure> {
+        if self.min <= value && value <= self.max {
+            Ok(())
+        } else {
+            Err(Failure::OutOfRange {
+                value,
+                bounds: *self,
+            })
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum FieldRule {
+    Year {
+        min: i64,
+        max: i64,
+    },
+    // A number directly followed by one of the units, each with its own bounds.
+    Measure {
+        units: BTreeMap<String, Bounds>,
+    },
+    // Characters from a set of single chars and `a-z` style ranges, optionally after a prefix.
+    Charset {
+        #[serde(default)]
+        prefix: String,
+        charset: String,
+        length: Option<usize>,
+    },
+    OneOf {
+        values: Vec<String>,
+    },
+    Any,
+}
+
+fn required_by_default() -> bool {
+    true
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct FieldSchema {
+    pub name: String,
+    #[serde(default = "required_by_default")]
+    pub required: bool,
+    #[serde(flatten)]
+    pub rule: FieldRule,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct Schema {
+    pub fields: Vec<FieldSchema>,
+}
+
+#[derive(Debug)]
+pub enum SchemaError {
+    Toml(toml::de::Error),
+    UnknownField(String),
+    InvalidCharset { field: String, charset: String },
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchemaError::Toml(e) => write!(f, "invalid schema: {}", e),
+            SchemaError::UnknownField(name) => write!(f, "unknown passport field '{}'", name),
+            SchemaError::InvalidCharset { field, charset } => {
+                write!(f, "field '{}': invalid charset '{}'", field, charset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Failure {
+    Missing,
+    NotAYear,
+    NotANumber,
+    OutOfRange { value: i64, bounds: Bounds },
+    UnknownUnit { units: Vec<String> },
+    MissingPrefix(String),
+    InvalidLength { expected: usize, found: usize },
+    InvalidChar(char),
+    NotAllowed { values: Vec<String> },
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Failure::Missing => write!(f, "missing"),
+            Failure::NotAYear => write!(f, "expected a four digit year"),
+            Failure::NotANumber => write!(f, "expected a number"),
+            Failure::OutOfRange { value, bounds } => write!(
+                f,
+                "{} is not between {} and {}",
+                value, bounds.min, bounds.max
+            ),
+            Failure::UnknownUnit { units } => {
+                write!(f, "expected a unit of {}", units.join(", "))
+            }
+            Failure::MissingPrefix(prefix) => write!(f, "expected prefix '{}'", prefix),
+            Failure::InvalidLength { expected, found } => {
+                write!(f, "expected {} characters, found {}", expected, found)
+            }
+            Failure::InvalidChar(c) => write!(f, "invalid character {:?}", c),
+            Failure::NotAllowed { values } => write!(f, "expected one of {}", values.join(", ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldFailure {
+    pub field: String,
+    pub reason: Failure,
+}
+
+impl fmt::Display for FieldFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.reason)
+    }
+}
+
+// Splits a charset like `0-9a-f#` into inclusive ranges, or `None` if a range is reversed or
+// left open.
+fn parse_charset(charset: &str) -> Option<Vec<(char, char)>> {
+    let chars = charset.chars().collect::<Vec<_>>();
+    let mut ranges = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        if chars.get(i + 1) == Some(&'-') {
+            let end = *chars.get(i + 2)?;
+            if end < chars[i] {
+                return None;
+            }
+            ranges.push((chars[i], end));
+            i += 3;
+        } else {
+            ranges.push((chars[i], chars[i]));
+            i += 1;
+        }
+    }
+    Some(ranges)
+}
+
+fn parse_number(digits: &str) -> Option<i64> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse::<i64>().ok()
+}
+
+impl FieldRule {
+    fn check(&self, value: &str) -> Result<(), Failure> {
+        match self {
+            FieldRule::Year { min, max } => {
+                let year = Some(value)
+                    .filter(|v| v.len() == 4)
+                    .and_then(parse_number)
+                    .ok_or(Failure::NotAYear)?;
+                Bounds {
+                    min: *min,
+                    max: *max,
+                }
+                .check(year)
+            }
+            FieldRule::Measure { units } => {
+                // The longest matching unit wins, so "min" is not read as "m" + "in".
+                let (number, bounds) = units
+                    .iter()
+                    .filter(|(unit, _)| value.ends_with(unit.as_str()))
+                    .max_by_key(|(unit, _)| unit.len())
+                    .map(|(unit, bounds)| (&value[..value.len() - unit.len()], bounds))
+                    .ok_or_else(|| Failure::UnknownUnit {
+                        units: units.keys().cloned().collect(),
+                    })?;
+                bounds.check(parse_number(number).ok_or(Failure::NotANumber)?)
+            }
+            FieldRule::Charset {
+                prefix,
+                charset,
+                length,
+            } => {
+                if !value.starts_with(prefix.as_str()) {
+                    return Err(Failure::MissingPrefix(prefix.clone()));
+                }
+                let rest = &value[prefix.len()..];
+                if let Some(&expected) = length.as_ref() {
+                    let found = rest.chars().count();
+                    if found != expected {
+                        return Err(Failure::InvalidLength { expected, found });
+                    }
+                }
+                let ranges = parse_charset(charset).unwrap_or_default();
+                match rest
+                    .chars()
+                    .find(|&c| !ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi))
+                {
+                    Some(c) => Err(Failure::InvalidChar(c)),
+                    None => Ok(()),
+                }
+            }
+            FieldRule::OneOf { values } => {
+                if values.iter().any(|v| v == value) {
+                    Ok(())
+                } else {
+                    Err(Failure::NotAllowed {
+                        values: values.clone(),
+                    })
+                }
+            }
+            FieldRule::Any => Ok(()),
+        }
+    }
+}
+
+impl Schema {
+    pub fn from_toml(input: &str) -> Result<Self, SchemaError> {
+        let schema: Schema = toml::from_str(input).map_err(SchemaError::Toml)?;
+        for field in &schema.fields {
+            if !Passport::FIELDS.contains(&field.name.as_str()) {
+                return Err(SchemaError::UnknownField(field.name.clone()));
+            }
+            if let FieldRule::Charset { charset, .. } = &field.rule {
+                if parse_charset(charset).is_none() {
+                    return Err(SchemaError::InvalidCharset {
+                        field: field.name.clone(),
+                        charset: charset.clone(),
+                    });
+                }
+            }
+        }
+        Ok(schema)
+    }
+
+    pub fn default_schema() -> Self {
+        Schema::from_toml(DEFAULT_SCHEMA).unwrap()
+    }
+
+    // Checks every field of the schema, in schema order, and returns the ones that failed.
+    pub fn validate(&self, passport: &Passport) -> Vec<FieldFailure> {
+        self.fields
+            .iter()
+            .filter_map(|field| {
+                let result = match passport.get(&field.name) {
+                    Some(value) => field.rule.check(value),
+                    None if field.required => Err(Failure::Missing),
+                    None => Ok(()),
+                };
+                result.err().map(|reason| FieldFailure {
+                    field: field.name.clone(),
+                    reason,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{Bounds, Failure, FieldFailure, FieldRule, Schema, SchemaError};
+    use crate::day4::Passport;
+
+    fn failures(schema: &Schema, passport: &str) -> Vec<String> {
+        schema
+            .validate(&Passport::from_str(passport).unwrap())
+            .iter()
+            .map(|f| f.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn default_schema() {
+        let schema = Schema::default_schema();
+        assert_eq!(schema.fields.len(), 8);
+        assert_eq!(
+            schema.fields[0].rule,
+            FieldRule::Year {
+                min: 1920,
+                max: 2002
+            }
+        );
+        assert!(!schema.fields[7].required);
+    }
+
+    #[test]
+    fn reports_every_failing_field() {
+        let schema = Schema::default_schema();
+        assert_eq!(
+            failures(
+                &schema,
+                "eyr:1972 cid:100 hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926"
+            ),
+            vec![
+                "eyr: 1972 is not between 2020 and 2030",
+                "hgt: expected a unit of cm, in",
+                "pid: expected 9 characters, found 5",
+            ]
+        );
+        assert_eq!(
+            failures(
+                &schema,
+                "hgt:59cm ecl:zzz hcl:#74454a pid:0355641237 byr:20071"
+            ),
+            vec![
+                "byr: expected a four digit year",
+                "iyr: missing",
+                "eyr: missing",
+                "hgt: 59 is not between 150 and 193",
+                "ecl: expected one of amb, blu, brn, gry, grn, hzl, oth",
+                "pid: expected 9 characters, found 10",
+            ]
+        );
+        assert_eq!(
+            Schema::default_schema().validate(
+                &Passport::from_str(
+                    "hcl:74454a hgt:xxin byr:2000 iyr:2015 eyr:2025 ecl:brn pid:00000000a"
+                )
+                .unwrap()
+            ),
+            vec![
+                FieldFailure {
+                    field: "hgt".to_string(),
+                    reason: Failure::NotANumber
+                },
+                FieldFailure {
+                    field: "hcl".to_string(),
+                    reason: Failure::MissingPrefix("#".to_string())
+                },
+                FieldFailure {
+                    field: "pid".to_string(),
+                    reason: Failure::InvalidChar('a')
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_schema() {
+        let schema = Schema::from_toml(
+            r#"
+[[fields]]
+name = "hgt"
+type = "measure"
+units = { m = { min = 1, max = 3 } }
+
+[[fields]]
+name = "cid"
+type = "charset"
+charset = "a-cx"
+required = false
+"#,
+        )
+        .unwrap();
+        assert_eq!(failures(&schema, "hgt:2m"), Vec::<String>::new());
+        assert_eq!(failures(&schema, "hgt:2m cid:abxc"), Vec::<String>::new());
+        assert_eq!(
+            failures(&schema, "cid:abd"),
+            vec!["hgt: missing", "cid: invalid character 'd'"]
+        );
+        assert_eq!(
+            schema.fields[0].rule,
+            FieldRule::Measure {
+                units: vec![("m".to_string(), Bounds { min: 1, max: 3 })]
+                    .into_iter()
+                    .collect()
+            }
+        );
+    }
+
+    #[test]
+    fn overlapping_units() {
+        let schema = Schema::from_toml(
+            r#"
+[[fields]]
+name = "hgt"
+type = "measure"
+units = { in = { min = 1, max = 5 }, min = { min = 10, max = 20 } }
+"#,
+        )
+        .unwrap();
+        assert_eq!(failures(&schema, "hgt:10min"), Vec::<String>::new());
+        assert_eq!(failures(&schema, "hgt:3in"), Vec::<String>::new());
+        assert_eq!(
+            failures(&schema, "hgt:3min"),
+            vec!["hgt: 3 is not between 10 and 20"]
+        );
+    }
+
+    #[test]
+    fn invalid_schemas() {
+        assert!(matches!(
+            Schema::from_toml("[[fields]]\nname = \"byr\"\ntype = \"date\""),
+            Err(SchemaError::Toml(_))
+        ));
+        assert!(matches!(
+            Schema::from_toml("[[fields]]\nname = \"xyz\"\ntype = \"any\""),
+            Err(SchemaError::UnknownField(name)) if name == "xyz"
+        ));
+        assert!(matches!(
+            Schema::from_toml("[[fields]]\nname = \"pid\"\ntype = \"charset\"\ncharset = \"9-0\""),
+            Err(SchemaError::InvalidCharset { .. })
+        ));
+    }
+}
@@ -0,0 +1,382 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use super::schema::{Failure, FieldFailure, Schema};
+use super::Passport;
+
+// The types below only check what they need to hold a value. Bounds and allowed values come from
+// the schema, which is checked first.
+fn parse_digits(digits: &str) -> Option<u32> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse::<u32>().ok()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Year(pub u16);
+
+impl FromStr for Year {
+    type Err = Failure;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Some(value)
+            .filter(|v| v.len() == 4)
+            .and_then(parse_digits)
+            .map(|year| Year(year as u16))
+            .ok_or(Failure::NotAYear)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Height {
+    Cm(u32),
+    In(u32),
+}
+
+impl FromStr for Height {
+    type Err = Failure;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let number = |digits: &str| parse_digits(digits).ok_or(Failure::NotANumber);
+        if let Some(digits) = value.strip_suffix("cm") {
+            number(digits).map(Height::Cm)
+        } else if let Some(digits) = value.strip_suffix("in") {
+            number(digits).map(Height::In)
+        } else {
+            Err(Failure::UnknownUnit {
+                units: vec!["cm".to_string(), "in".to_string()],
+            })
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HairColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl FromStr for HairColor {
+    type Err = Failure;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let hex = value
+            .strip_prefix('#')
+            .ok_or_else(|| Failure::MissingPrefix("#".to_string()))?;
+        let found = hex.chars().count();
+        if found != 6 {
+            return Err(Failure::InvalidLength { expected: 6, found });
+        }
+        if let Some(c) = hex.chars().find(|c| !matches!(c, '0'..='9' | 'a'..='f')) {
+            return Err(Failure::InvalidChar(c));
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+        Ok(HairColor {
+            r: channel(0),
+            g: channel(2),
+            b: channel(4),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EyeColor {
+    Amber,
+    Blue,
+    Brown,
+    Gray,
+    Green,
+    Hazel,
+    Other,
+}
+
+impl EyeColor {
+    const CODES: [(&'static str, EyeColor); 7] = [
+        ("amb", EyeColor::Amber),
+        ("blu", EyeColor::Blue),
+        ("brn", EyeColor::Brown),
+        ("gry", EyeColor::Gray),
+        ("grn", EyeColor::Green),
+        ("hzl", EyeColor::Hazel),
+        ("oth", EyeColor::Other),
+    ];
+}
+
+impl FromStr for EyeColor {
+    type Err = Failure;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        EyeColor::CODES
+            .iter()
+            .find(|(code, _)| *code == value)
+            .map(|&(_, color)| color)
+            .ok_or_else(|| Failure::NotAllowed {
+                values: EyeColor::CODES
+                    .iter()
+                    .map(|(code, _)| code.to_string())
+                    .collect(),
+            })
+    }
+}
+
+// A nine digit number; leading zeroes are part of the format but not of the value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PassportId(pub u32);
+
+impl FromStr for PassportId {
+    type Err = Failure;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let found = value.chars().count();
+        if found != 9 {
+            return Err(Failure::InvalidLength { expected: 9, found });
+        }
+        match value.chars().find(|c| !c.is_ascii_digit()) {
+            Some(c) => Err(Failure::InvalidChar(c)),
+            None => Ok(PassportId(value.parse().unwrap())),
+        }
+    }
+}
+
+impl fmt::Display for PassportId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:09}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatedPassport {
+    pub birth_year: Year,
+    pub issue_year: Year,
+    pub expiration_year: Year,
+    pub height: Height,
+    pub hair_color: HairColor,
+    pub eye_color: EyeColor,
+    pub passport_id: PassportId,
+    pub country_id: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct InvalidPassport {
+    pub failures: Vec<FieldFailure>,
+}
+
+impl fmt::Display for InvalidPassport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let failures = self
+            .failures
+            .iter()
+            .map(|failure| failure.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "invalid passport: {}", failures.join("; "))
+    }
+}
+
+impl std::error::Error for InvalidPassport {}
+
+// Runs `parse` on a required field, recording a failure instead of stopping at the first one.
+fn field<T>(
+    failures: &mut Vec<FieldFailure>,
+    name: &str,
+    value: &Option<String>,
+    parse: impl FnOnce(&str) -> Result<T, Failure>,
+) -> Option<T> {
+    let result = match value {
+        Some(value) => parse(value),
+        None => Err(Failure::Missing),
+    };
+    match result {
+        Ok(parsed) => Some(parsed),
+        Err(reason) => {
+            failures.push(FieldFailure {
+                field: name.to_string(),
+                reason,
+            });
+            None
+        }
+    }
+}
+
+impl ValidatedPassport {
+    // Checks the passport against `schema`, then converts the fields. A schema that lets through
+    // values the types cannot hold, such as a height in a unit other than cm or in, still fails.
+    pub fn new(passport: &Passport, schema: &Schema) -> Result<Self, InvalidPassport> {
+        let mut failures = schema.validate(passport);
+        if !failures.is_empty() {
+            return Err(InvalidPassport { failures });
+        }
+        let birth_year = field(&mut failures, "byr", &passport.byr, str::parse);
+        let issue_year = field(&mut failures, "iyr", &passport.iyr, str::parse);
+        let expiration_year = field(&mut failures, "eyr", &passport.eyr, str::parse);
+        let height = field(&mut failures, "hgt", &passport.hgt, str::parse);
+        let hair_color = field(&mut failures, "hcl", &passport.hcl, str::parse);
+        let eye_color = field(&mut failures, "ecl", &passport.ecl, str::parse);
+        let passport_id = field(&mut failures, "pid", &passport.pid, str::parse);
+
+        match (
+            birth_year,
+            issue_year,
+            expiration_year,
+            height,
+            hair_color,
+            eye_color,
+            passport_id,
+        ) {
+            (
+                Some(birth_year),
+                Some(issue_year),
+                Some(expiration_year),
+                Some(height),
+                Some(hair_color),
+                Some(eye_color),
+                Some(passport_id),
+            ) => Ok(ValidatedPassport {
+                birth_year,
+                issue_year,
+                expiration_year,
+                height,
+                hair_color,
+                eye_color,
+                passport_id,
+                country_id: passport.cid.clone(),
+            }),
+            _ => Err(InvalidPassport { failures }),
+        }
+    }
+}
+
+// Converts against the default schema, collecting every field error at once.
+impl TryFrom<&Passport> for ValidatedPassport {
+    type Error = InvalidPassport;
+
+    fn try_from(passport: &Passport) -> Result<Self, Self::Error> {
+        ValidatedPassport::new(passport, &Schema::default_schema())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::convert::TryFrom;
+
+    use super::{EyeColor, HairColor, Height, PassportId, ValidatedPassport, Year};
+    use crate::day4::schema::{Failure, Schema};
+    use crate::day4::Passport;
+
+    fn validate(input: &str) -> Result<ValidatedPassport, Vec<String>> {
+        ValidatedPassport::try_from(&Passport::from_str(input).unwrap()).map_err(|e| {
+            e.failures
+                .iter()
+                .map(|failure| failure.to_string())
+                .collect()
+        })
+    }
+
+    #[test]
+    fn converts_valid_passport() {
+        let passport =
+            validate("pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f")
+                .unwrap();
+        assert_eq!(
+            passport,
+            ValidatedPassport {
+                birth_year: Year(1980),
+                issue_year: Year(2012),
+                expiration_year: Year(2030),
+                height: Height::In(74),
+                hair_color: HairColor {
+                    r: 0x62,
+                    g: 0x3a,
+                    b: 0x2f
+                },
+                eye_color: EyeColor::Green,
+                passport_id: PassportId(87499704),
+                country_id: None,
+            }
+        );
+        assert_eq!(passport.passport_id.to_string(), "087499704");
+        assert_eq!(
+            validate(
+                "pid:545766238 hgt:164cm ecl:hzl iyr:2015 eyr:2022 byr:2001 hcl:#888785 cid:88"
+            )
+            .unwrap()
+            .country_id,
+            Some("88".to_string())
+        );
+    }
+
+    #[test]
+    fn collects_all_field_errors() {
+        assert_eq!(
+            validate("eyr:1972 cid:100 hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926"),
+            Err(vec![
+                "eyr: 1972 is not between 2020 and 2030".to_string(),
+                "hgt: expected a unit of cm, in".to_string(),
+                "pid: expected 9 characters, found 5".to_string(),
+            ])
+        );
+        assert_eq!(
+            validate("hcl:#74454 ecl:zzz pid:00000000a hgt:200in byr:02x0"),
+            Err(vec![
+                "byr: expected a four digit year".to_string(),
+                "iyr: missing".to_string(),
+                "eyr: missing".to_string(),
+                "hgt: 200 is not between 59 and 76".to_string(),
+                "hcl: expected 6 characters, found 5".to_string(),
+                "ecl: expected one of amb, blu, brn, gry, grn, hzl, oth".to_string(),
+                "pid: invalid character 'a'".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn field_types() {
+        assert_eq!("150cm".parse::<Height>(), Ok(Height::Cm(150)));
+        assert_eq!("cm".parse::<Height>(), Err(Failure::NotANumber));
+        assert_eq!(
+            "#ABCDEF".parse::<HairColor>(),
+            Err(Failure::InvalidChar('A'))
+        );
+        assert_eq!("oth".parse::<EyeColor>(), Ok(EyeColor::Other));
+        assert_eq!("000000001".parse::<PassportId>(), Ok(PassportId(1)));
+    }
+
+    #[test]
+    fn follows_the_schema() {
+        let schema = Schema::from_toml(
+            r#"
+[[fields]]
+name = "byr"
+type = "year"
+min = 1900
+max = 1910
+
+[[fields]]
+name = "hgt"
+type = "measure"
+units = { cm = { min = 1, max = 9 }, mm = { min = 1, max = 9 } }
+"#,
+        )
+        .unwrap();
+        let convert = |input: &str| {
+            ValidatedPassport::new(&Passport::from_str(input).unwrap(), &schema)
+                .map(|passport| (passport.birth_year, passport.height))
+                .map_err(|e| e.to_string())
+        };
+        let rest = "iyr:2012 eyr:2030 hcl:#623a2f ecl:grn pid:087499704";
+        assert_eq!(
+            convert(&format!("byr:1905 hgt:5cm {}", rest)),
+            Ok((Year(1905), Height::Cm(5)))
+        );
+        assert_eq!(
+            convert(&format!("byr:1980 hgt:5cm {}", rest)),
+            Err("invalid passport: byr: 1980 is not between 1900 and 1910".to_string())
+        );
+        assert_eq!(
+            convert(&format!("byr:1905 hgt:5mm {}", rest)),
+            Err("invalid passport: hgt: expected a unit of cm, in".to_string())
+        );
+    }
+}
@@ -1,69 +1,263 @@
-use core::panic;
+use std::fmt;
 
-static NUM_ROWS: i32 = 128;
-static NUM_COLS: i32 = 8;
+use seat_map::SeatMap;
 
-#[aoc_generator(day5)]
-fn generator_input(input: &str) -> Vec<String> {
-    input.lines().map(|a| a.to_string()).collect()
+pub mod cli;
+mod seat_map;
+
+#[derive(Debug, PartialEq)]
+enum CodecError {
+    NotAPowerOfTwo(u32),
+    AmbiguousLetters(char),
+    // Seat IDs have to fit in 32 bits.
+    TooManySeats {
+        rows: u32,
+        cols: u32,
+    },
+    InvalidLength {
+        expected: usize,
+        found: usize,
+    },
+    InvalidLetter {
+        position: usize,
+        found: char,
+        expected: (char, char),
+    },
+    SeatOutOfRange(u32),
+    // The rows of a seat map have to be rows the codec can address.
+    RowsOutOfRange {
+        start: u32,
+        end: u32,
+        rows: u32,
+    },
 }
 
-fn calculate_row(row_info: &[char]) -> i32 {
-    search_binary(row_info, (0, NUM_ROWS - 1))
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CodecError::NotAPowerOfTwo(n) => write!(f, "{} is not a power of two", n),
+            CodecError::AmbiguousLetters(c) => write!(f, "{:?} is used for both halves", c),
+            CodecError::TooManySeats { rows, cols } => {
+                write!(f, "{} rows of {} seats do not fit in 32 bits", rows, cols)
+            }
+            CodecError::InvalidLength { expected, found } => {
+                write!(f, "expected {} letters, found {}", expected, found)
+            }
+            CodecError::InvalidLetter {
+                position,
+                found,
+                expected,
+            } => write!(
+                f,
+                "letter {}: expected {:?} or {:?}, found {:?}",
+                position, expected.0, expected.1, found
+            ),
+            CodecError::SeatOutOfRange(id) => write!(f, "seat {} is not on the plane", id),
+            CodecError::RowsOutOfRange { start, end, rows } => write!(
+                f,
+                "rows {}..{} are not within the {} rows of the plane",
+                start, end, rows
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+#[derive(Debug, PartialEq)]
+enum ManifestError {
+    Codec(CodecError),
+    NoEnclosedSeat,
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ManifestError::Codec(e) => write!(f, "{}", e),
+            ManifestError::NoEnclosedSeat => {
+                write!(f, "no empty seat between two occupied seats")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+impl From<CodecError> for ManifestError {
+    fn from(e: CodecError) -> Self {
+        ManifestError::Codec(e)
+    }
 }
 
-fn calculate_col(col_info: &[char]) -> i32 {
-    search_binary(col_info, (0, NUM_COLS - 1))
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Seat {
+    row: u32,
+    col: u32,
 }
 
-fn calculate_seat_id(boarding_pass: &str) -> i32 {
-    let row = calculate_row(&boarding_pass[..7].chars().collect::<Vec<_>>());
-    let col = calculate_col(&boarding_pass[7..].chars().collect::<Vec<_>>());
-    row * 8 + col
+// A boarding pass is a binary number: first the row bits, then the column bits. Each alphabet is
+// (lower half, upper half), i.e. the letters for a 0 and a 1 bit.
+#[derive(Debug, Clone, PartialEq)]
+struct BoardingPassCodec {
+    rows: u32,
+    cols: u32,
+    row_letters: (char, char),
+    col_letters: (char, char),
 }
 
-fn search_binary(partitioning: &[char], seats: (i32, i32)) -> i32 {
-    if partitioning.len() == 0 {
-        assert_eq!(seats.0, seats.1);
-        return seats.0;
+impl BoardingPassCodec {
+    fn new(
+        rows: u32,
+        cols: u32,
+        row_letters: (char, char),
+        col_letters: (char, char),
+    ) -> Result<Self, CodecError> {
+        for &n in &[rows, cols] {
+            if !n.is_power_of_two() {
+                return Err(CodecError::NotAPowerOfTwo(n));
+            }
+        }
+        // Also bounds every decoded row, column and seat ID, so none of them can overflow.
+        if rows.checked_mul(cols).is_none() {
+            return Err(CodecError::TooManySeats { rows, cols });
+        }
+        for &(low, high) in &[row_letters, col_letters] {
+            if low == high {
+                return Err(CodecError::AmbiguousLetters(low));
+            }
+        }
+        Ok(BoardingPassCodec {
+            rows,
+            cols,
+            row_letters,
+            col_letters,
+        })
+    }
+
+    fn standard() -> Self {
+        BoardingPassCodec::new(128, 8, ('F', 'B'), ('L', 'R')).unwrap()
+    }
+
+    fn row_bits(&self) -> usize {
+        self.rows.trailing_zeros() as usize
+    }
+
+    fn col_bits(&self) -> usize {
+        self.cols.trailing_zeros() as usize
+    }
+
+    fn seat_count(&self) -> u32 {
+        self.rows * self.cols
+    }
+
+    fn seat_id(&self, seat: Seat) -> u32 {
+        seat.row * self.cols + seat.col
+    }
+
+    fn seat_from_id(&self, id: u32) -> Result<Seat, CodecError> {
+        if id >= self.seat_count() {
+            return Err(CodecError::SeatOutOfRange(id));
+        }
+        Ok(Seat {
+            row: id / self.cols,
+            col: id % self.cols,
+        })
+    }
+
+    fn decode_bits(
+        letters: &[char],
+        offset: usize,
+        alphabet: (char, char),
+    ) -> Result<u32, CodecError> {
+        letters
+            .iter()
+            .enumerate()
+            .try_fold(0, |value, (i, &letter)| match letter {
+                l if l == alphabet.0 => Ok(value << 1),
+                l if l == alphabet.1 => Ok(value << 1 | 1),
+                found => Err(CodecError::InvalidLetter {
+                    position: offset + i + 1,
+                    found,
+                    expected: alphabet,
+                }),
+            })
     }
-    let half = seats.0 + (seats.1 - seats.0) / 2;
-    match partitioning.first().unwrap() {
-        'F' | 'L' => search_binary(&partitioning[1..], (seats.0, half)),
-        'B' | 'R' => search_binary(&partitioning[1..], (half + 1, seats.1)),
-        _ => panic!("This can't happen :O Abort mission, prepare for emergency landing!"),
+
+    fn encode_bits(value: u32, bits: usize, alphabet: (char, char)) -> impl Iterator<Item = char> {
+        (0..bits).rev().map(move |bit| {
+            if value >> bit & 1 == 1 {
+                alphabet.1
+            } else {
+                alphabet.0
+            }
+        })
     }
+
+    fn decode(&self, pass: &str) -> Result<Seat, CodecError> {
+        let letters = pass.chars().collect::<Vec<_>>();
+        let expected = self.row_bits() + self.col_bits();
+        if letters.len() != expected {
+            return Err(CodecError::InvalidLength {
+                expected,
+                found: letters.len(),
+            });
+        }
+        let (row, col) = letters.split_at(self.row_bits());
+        Ok(Seat {
+            row: Self::decode_bits(row, 0, self.row_letters)?,
+            col: Self::decode_bits(col, self.row_bits(), self.col_letters)?,
+        })
+    }
+
+    fn decode_id(&self, pass: &str) -> Result<u32, CodecError> {
+        self.decode(pass).map(|seat| self.seat_id(seat))
+    }
+
+    fn encode(&self, id: u32) -> Result<String, CodecError> {
+        let seat = self.seat_from_id(id)?;
+        Ok(
+            Self::encode_bits(seat.row, self.row_bits(), self.row_letters)
+                .chain(Self::encode_bits(
+                    seat.col,
+                    self.col_bits(),
+                    self.col_letters,
+                ))
+                .collect(),
+        )
+    }
+}
+
+#[aoc_generator(day5)]
+fn generator_input(input: &str) -> Vec<String> {
+    input.lines().map(|a| a.to_string()).collect()
+}
+
+fn decode_all(input: &[String]) -> Result<Vec<u32>, CodecError> {
+    let codec = BoardingPassCodec::standard();
+    input.iter().map(|pass| codec.decode_id(pass)).collect()
 }
 
 #[aoc(day5, part1)]
-fn part1(input: &[String]) -> i32 {
-    input
-        .iter()
-        .map(|pass| calculate_seat_id(pass))
-        .max()
-        .unwrap()
+fn part1(input: &[String]) -> Result<u32, CodecError> {
+    Ok(decode_all(input)?.into_iter().max().unwrap_or(0))
 }
 
 #[aoc(day5, part2)]
-fn part2(input: &[String]) -> i32 {
-    let mut ids = input
-        .iter()
-        .map(|pass| calculate_seat_id(pass))
-        .collect::<Vec<_>>();
-    ids.sort();
-    let mut my_id = ids[1];
-    for id in ids[1..].iter() {
-        if &my_id != id {
-            return my_id;
-        }
-        my_id += 1;
-    }
-    panic!("This can't happen :O Abort mission, prepare for emergency landing!");
+fn part2(input: &[String]) -> Result<u32, ManifestError> {
+    let codec = BoardingPassCodec::standard();
+    let rows = 0..codec.rows;
+    let map = SeatMap::from_passes(codec, rows, input)?;
+    map.enclosed_empty_seats()
+        .first()
+        .copied()
+        .ok_or(ManifestError::NoEnclosedSeat)
 }
 
 #[cfg(test)]
 pub mod tests {
-    use super::{calculate_col, calculate_row, calculate_seat_id, generator_input, part1};
+    use super::{
+        generator_input, part1, part2, BoardingPassCodec, CodecError, ManifestError, Seat,
+    };
     static INPUT_RAW: &str = r#"BFFFBBFRRR
 FFFBBBFRRR
 BBFFBBFRLL"#;
@@ -75,28 +269,97 @@ BBFFBBFRLL"#;
     }
 
     #[test]
-    fn test_calculate_row() {
-        let row_info = "FBFBBFF";
-        let chars = row_info.chars().collect::<Vec<_>>();
-        assert_eq!(calculate_row(&chars), 44);
+    fn test_decode_seat() {
+        let codec = BoardingPassCodec::standard();
+        assert_eq!(codec.decode("FBFBBFFRLR"), Ok(Seat { row: 44, col: 5 }));
+        assert_eq!(codec.decode("BBFFBBFRLL"), Ok(Seat { row: 102, col: 4 }));
     }
 
     #[test]
-    fn test_calculate_col() {
-        let col_info = "RLR";
-        let chars = col_info.chars().collect::<Vec<_>>();
-        assert_eq!(calculate_col(&chars), 5);
+    fn test_calculate_seat_id() {
+        let boarding_pass = "FBFBBFFRLR";
+        assert_eq!(
+            BoardingPassCodec::standard().decode_id(boarding_pass),
+            Ok(357)
+        );
     }
 
     #[test]
-    fn test_calculate_seat_id() {
-        let boarding_pass = "FBFBBFFRLR";
-        assert_eq!(calculate_seat_id(boarding_pass), 357);
+    fn encode_roundtrip() {
+        let codec = BoardingPassCodec::standard();
+        assert_eq!(codec.encode(357), Ok("FBFBBFFRLR".to_string()));
+        for id in 0..codec.seat_count() {
+            assert_eq!(codec.decode_id(&codec.encode(id).unwrap()), Ok(id));
+        }
+        assert_eq!(codec.encode(1024), Err(CodecError::SeatOutOfRange(1024)));
+    }
+
+    #[test]
+    fn custom_geometry() {
+        let codec = BoardingPassCodec::new(16, 4, ('0', '1'), ('a', 'b')).unwrap();
+        assert_eq!(codec.decode("1010ba"), Ok(Seat { row: 10, col: 2 }));
+        assert_eq!(codec.decode_id("1010ba"), Ok(42));
+        assert_eq!(codec.encode(42), Ok("1010ba".to_string()));
+        assert_eq!(
+            BoardingPassCodec::new(100, 8, ('F', 'B'), ('L', 'R')),
+            Err(CodecError::NotAPowerOfTwo(100))
+        );
+        assert_eq!(
+            BoardingPassCodec::new(128, 8, ('F', 'F'), ('L', 'R')),
+            Err(CodecError::AmbiguousLetters('F'))
+        );
+        assert_eq!(
+            BoardingPassCodec::new(1 << 20, 1 << 12, ('F', 'B'), ('L', 'R')),
+            Err(CodecError::TooManySeats {
+                rows: 1 << 20,
+                cols: 1 << 12
+            })
+        );
+        let widest = BoardingPassCodec::new(1 << 16, 1 << 15, ('0', '1'), ('0', '1')).unwrap();
+        assert_eq!(widest.seat_count(), 1 << 31);
+        let last = "1".repeat(31);
+        assert_eq!(widest.decode_id(&last), Ok(u32::MAX >> 1));
+        assert_eq!(widest.encode(u32::MAX >> 1), Ok(last));
+    }
+
+    #[test]
+    fn rejects_malformed_passes() {
+        let codec = BoardingPassCodec::standard();
+        assert_eq!(
+            codec.decode("FBFBBFFRL"),
+            Err(CodecError::InvalidLength {
+                expected: 10,
+                found: 9
+            })
+        );
+        assert_eq!(
+            codec.decode("FBFBBFFRXR"),
+            Err(CodecError::InvalidLetter {
+                position: 9,
+                found: 'X',
+                expected: ('L', 'R')
+            })
+        );
+        assert_eq!(
+            codec.decode("FBFRBFFRLR").unwrap_err().to_string(),
+            "letter 4: expected 'F' or 'B', found 'R'"
+        );
+        assert!(part1(&["FBFBBFFRLR".to_string(), "nope".to_string()]).is_err());
     }
 
     #[test]
     fn day5_part1() {
         let input = generator_input(&INPUT_RAW);
-        assert_eq!(part1(&input), 820);
+        assert_eq!(part1(&input), Ok(820));
+    }
+
+    #[test]
+    fn day5_part2() {
+        let passes = ["FBFBBFFRLL", "FBFBBFFRRL", "BBFFBBFRLL"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(part2(&passes), Ok(357));
+        assert_eq!(part2(&passes[..1]), Err(ManifestError::NoEnclosedSeat));
     }
 }
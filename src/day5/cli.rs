@@ -0,0 +1,159 @@
+use super::seat_map::SeatMap;
+use super::{generator_input, BoardingPassCodec};
+
+pub const USAGE: &str = "usage: seats [--input FILE] COMMAND
+commands, for the standard plane of 128 rows of 8 seats:
+    decode        row, column and seat ID of every boarding pass in the input
+    encode ID...  the boarding pass for each seat ID
+    manifest [FIRST..END]
+                  cabin map of the rows FIRST up to END, all of them by default, followed by the
+                  empty seats, the empty seats between two occupied ones, repeated passes and
+                  passes for rows outside the cabin";
+
+fn join(ids: &[u32]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn manifest(codec: BoardingPassCodec, rows: Option<&str>, input: &str) -> Result<String, String> {
+    let rows = match rows {
+        None => 0..codec.rows,
+        Some(rows) => {
+            let mut bounds = rows.splitn(2, "..").map(|bound| bound.parse().ok());
+            match (bounds.next().flatten(), bounds.next().flatten()) {
+                (Some(first), Some(end)) => first..end,
+                _ => return Err(format!("invalid rows '{}'\n{}", rows, USAGE)),
+            }
+        }
+    };
+    let passes = generator_input(input);
+    let map = SeatMap::from_passes(codec, rows, &passes).map_err(|e| e.to_string())?;
+    let mut output = map.to_string();
+    output.push_str(&format!("empty seats: {}\n", join(&map.empty_seats())));
+    output.push_str(&format!(
+        "enclosed empty seats: {}\n",
+        join(&map.enclosed_empty_seats())
+    ));
+    for duplicate in map.duplicates() {
+        output.push_str(&format!(
+            "line {}: {} repeats line {}\n",
+            duplicate.boarded.index + 1,
+            duplicate.boarded.pass,
+            duplicate.first_index + 1
+        ));
+    }
+    for outside in map.outside() {
+        output.push_str(&format!(
+            "line {}: {} is for row {}, outside the cabin\n",
+            outside.index + 1,
+            outside.pass,
+            outside.seat.row
+        ));
+    }
+    Ok(output)
+}
+
+// Runs one command against the boarding passes in `input` and returns what it prints, or the
+// usage problem or first bad pass as a message. `args` does not include the program name or the
+// input file option.
+pub fn run(args: &[String], input: &str) -> Result<String, String> {
+    let codec = BoardingPassCodec::standard();
+    let args = args.iter().map(|a| a.as_str()).collect::<Vec<_>>();
+    let mut output = String::new();
+    match args.as_slice() {
+        ["decode"] => {
+            for (i, pass) in generator_input(input).iter().enumerate() {
+                let seat = codec
+                    .decode(pass)
+                    .map_err(|e| format!("line {}: {}", i + 1, e))?;
+                output.push_str(&format!(
+                    "{}: row {}, column {}, seat ID {}\n",
+                    pass,
+                    seat.row,
+                    seat.col,
+                    codec.seat_id(seat)
+                ));
+            }
+        }
+        ["encode", ids @ ..] if !ids.is_empty() => {
+            for id in ids {
+                let pass = id
+                    .parse()
+                    .map_err(|_| format!("invalid seat ID '{}'", id))
+                    .and_then(|id| codec.encode(id).map_err(|e| e.to_string()))?;
+                output.push_str(&format!("{}: {}\n", id, pass));
+            }
+        }
+        ["manifest"] => return manifest(codec, None, input),
+        ["manifest", rows] => return manifest(codec, Some(rows), input),
+        [] => return Err(format!("missing command\n{}", USAGE)),
+        [command, ..] => {
+            return Err(format!(
+                "unknown command or wrong arguments for '{}'\n{}",
+                command, USAGE
+            ))
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::run;
+
+    fn run_args(args: &[&str], input: &str) -> Result<String, String> {
+        let args = args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        run(&args, input)
+    }
+
+    #[test]
+    fn decode_and_encode() {
+        let input = "FBFBBFFRLR\nBBFFBBFRLL";
+        assert_eq!(
+            run_args(&["decode"], input).unwrap(),
+            "FBFBBFFRLR: row 44, column 5, seat ID 357\n\
+             BBFFBBFRLL: row 102, column 4, seat ID 820\n"
+        );
+        assert_eq!(
+            run_args(&["decode"], "FBFBBFFRLR\nFBFBBFFRL").unwrap_err(),
+            "line 2: expected 10 letters, found 9"
+        );
+        assert_eq!(
+            run_args(&["encode", "357", "820"], "").unwrap(),
+            "357: FBFBBFFRLR\n820: BBFFBBFRLL\n"
+        );
+        assert_eq!(
+            run_args(&["encode", "1024"], "").unwrap_err(),
+            "seat 1024 is not on the plane"
+        );
+        assert!(run_args(&["encode"], "")
+            .unwrap_err()
+            .starts_with("unknown command or wrong arguments for 'encode'\nusage:"));
+    }
+
+    #[test]
+    fn manifest() {
+        let input = "FFFFFFBLLL\nFFFFFFBLLR\nFFFFFFBLRR\nFFFFFFBLLL\nFFFFFFFLLL";
+        let output = run_args(&["manifest", "1..2"], input).unwrap();
+        assert_eq!(
+            output,
+            "1 ##.# ....\n\
+             empty seats: 10, 12, 13, 14, 15\n\
+             enclosed empty seats: 10\n\
+             line 4: FFFFFFBLLL repeats line 1\n\
+             line 5: FFFFFFFLLL is for row 0, outside the cabin\n"
+        );
+        assert!(run_args(&["manifest"], input)
+            .unwrap()
+            .contains("\nenclosed empty seats: 10\n"));
+        assert_eq!(
+            run_args(&["manifest", "0..200"], input).unwrap_err(),
+            "rows 0..200 are not within the 128 rows of the plane"
+        );
+        assert!(run_args(&["manifest", "1-2"], input)
+            .unwrap_err()
+            .starts_with("invalid rows '1-2'\nusage:"));
+    }
+}
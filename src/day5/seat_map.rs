@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+use super::{BoardingPassCodec, CodecError, Seat};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoardedPass {
+    // 0-based position of the pass in the manifest.
+    pub index: usize,
+    pub pass: String,
+    pub seat: Seat,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicatePass {
+    pub boarded: BoardedPass,
+    pub first_index: usize,
+}
+
+// One bit per seat of the plane, indexed by seat ID. `rows` are the rows that actually exist;
+// the codec may be able to address more of them.
+pub struct SeatMap {
+    codec: BoardingPassCodec,
+    rows: Range<u32>,
+    seat_ids: Range<u32>,
+    occupied: Vec<u64>,
+    first_seen: HashMap<u32, usize>,
+    duplicates: Vec<DuplicatePass>,
+    outside: Vec<BoardedPass>,
+    boarded: usize,
+}
+
+impl SeatMap {
+    pub fn new(codec: BoardingPassCodec, rows: Range<u32>) -> Result<Self, CodecError> {
+        let seat_ids = match (
+            rows.start.checked_mul(codec.cols),
+            rows.end.checked_mul(codec.cols),
+        ) {
+            (Some(start), Some(end)) if rows.start <= rows.end && rows.end <= codec.rows => {
+                start..end
+            }
+            _ => {
+                return Err(CodecError::RowsOutOfRange {
+                    start: rows.start,
+                    end: rows.end,
+                    rows: codec.rows,
+                })
+            }
+        };
+        let words = codec.seat_count().div_ceil(64) as usize;
+        Ok(SeatMap {
+            codec,
+            rows,
+            seat_ids,
+            occupied: vec![0; words],
+            first_seen: HashMap::new(),
+            duplicates: vec![],
+            outside: vec![],
+            boarded: 0,
+        })
+    }
+
+    pub fn from_passes(
+        codec: BoardingPassCodec,
+        rows: Range<u32>,
+        passes: &[String],
+    ) -> Result<Self, CodecError> {
+        let mut map = SeatMap::new(codec, rows)?;
+        for pass in passes {
+            map.board(pass)?;
+        }
+        Ok(map)
+    }
+
+    // Malformed passes are rejected; duplicates and seats outside the plane are recorded but do
+    // not change the occupancy.
+    pub fn board(&mut self, pass: &str) -> Result<(), CodecError> {
+        let seat = self.codec.decode(pass)?;
+        let boarded = BoardedPass {
+            index: self.boarded,
+            pass: pass.to_string(),
+            seat,
+        };
+        self.boarded += 1;
+        let id = self.codec.seat_id(seat);
+        if !self.rows.contains(&seat.row) {
+            self.outside.push(boarded);
+        } else if let Some(&first_index) = self.first_seen.get(&id) {
+            self.duplicates.push(DuplicatePass {
+                boarded,
+                first_index,
+            });
+        } else {
+            self.first_seen.insert(id, boarded.index);
+            self.occupied[id as usize / 64] |= 1 << (id % 64);
+        }
+        Ok(())
+    }
+
+    pub fn is_occupied(&self, id: u32) -> bool {
+        id < self.codec.seat_count() && self.occupied[id as usize / 64] >> (id % 64) & 1 == 1
+    }
+
+    pub fn empty_seats(&self) -> Vec<u32> {
+        self.seat_ids
+            .clone()
+            .filter(|&id| !self.is_occupied(id))
+            .collect()
+    }
+
+    // Empty seats whose IDs are directly between two occupied seats.
+    pub fn enclosed_empty_seats(&self) -> Vec<u32> {
+        self.seat_ids
+            .clone()
+            .filter(|&id| {
+                id > 0
+                    && !self.is_occupied(id)
+                    && self.is_occupied(id - 1)
+                    && self.is_occupied(id + 1)
+            })
+            .collect()
+    }
+
+    pub fn duplicates(&self) -> &[DuplicatePass] {
+        &self.duplicates
+    }
+
+    pub fn outside(&self) -> &[BoardedPass] {
+        &self.outside
+    }
+}
+
+// One line per row: the row number, then '#' for occupied and '.' for empty seats with the
+// aisle in the middle.
+impl fmt::Display for SeatMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.rows.end.saturating_sub(1).to_string().len();
+        let aisle = self.codec.cols / 2;
+        for row in self.rows.clone() {
+            write!(f, "{:>width$} ", row, width = width)?;
+            for col in 0..self.codec.cols {
+                if col == aisle && col > 0 {
+                    write!(f, " ")?;
+                }
+                let id = self.codec.seat_id(Seat { row, col });
+                write!(f, "{}", if self.is_occupied(id) { '#' } else { '.' })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{BoardingPassCodec, CodecError, SeatMap};
+
+    fn small_map(passes: &[&str]) -> SeatMap {
+        let codec = BoardingPassCodec::new(8, 4, ('F', 'B'), ('L', 'R')).unwrap();
+        let passes = passes.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        SeatMap::from_passes(codec, 1..7, &passes).unwrap()
+    }
+
+    #[test]
+    fn occupancy_queries() {
+        // Row 1 is full, row 2 has seats 9 and 11 taken, row 3 only seat 15.
+        let map = small_map(&[
+            "FFBLL", "FFBLR", "FFBRL", "FFBRR", "FBFLR", "FBFRR", "FBBRR",
+        ]);
+        assert!(map.is_occupied(9));
+        assert!(!map.is_occupied(10));
+        assert!(!map.is_occupied(100));
+        assert_eq!(&map.empty_seats()[..4], &[8, 10, 12, 13]);
+        assert_eq!(map.empty_seats().len(), 24 - 7);
+        assert_eq!(map.enclosed_empty_seats(), vec![8, 10]);
+    }
+
+    #[test]
+    fn duplicates_and_outside() {
+        let map = small_map(&["FFBLL", "FFFLR", "FFBLL", "BBBRR", "FFBLL"]);
+        let duplicates = map
+            .duplicates()
+            .iter()
+            .map(|d| (d.boarded.index, d.first_index, d.boarded.pass.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(duplicates, vec![(2, 0, "FFBLL"), (4, 0, "FFBLL")]);
+        let outside = map
+            .outside()
+            .iter()
+            .map(|p| (p.index, p.seat.row))
+            .collect::<Vec<_>>();
+        assert_eq!(outside, vec![(1, 0), (3, 7)]);
+        assert_eq!(map.empty_seats().len(), 23);
+    }
+
+    #[test]
+    fn rejects_malformed_pass() {
+        let codec = BoardingPassCodec::new(8, 4, ('F', 'B'), ('L', 'R')).unwrap();
+        let mut map = SeatMap::new(codec, 0..8).unwrap();
+        assert!(map.board("FFBLX").is_err());
+        assert_eq!(map.empty_seats().len(), 32);
+    }
+
+    #[test]
+    fn rows_must_be_on_the_plane() {
+        let codec = BoardingPassCodec::new(8, 4, ('F', 'B'), ('L', 'R')).unwrap();
+        assert!(SeatMap::new(codec.clone(), 8..8).is_ok());
+        for &(start, end) in &[(0, 9), (5, 3), (1, u32::MAX)] {
+            assert_eq!(
+                SeatMap::new(codec.clone(), start..end).err(),
+                Some(CodecError::RowsOutOfRange {
+                    start,
+                    end,
+                    rows: 8
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn render_cabin() {
+        let map = small_map(&["FFBLL", "FFBLR", "FFBRL", "FFBRR", "FBFLR", "FBBRR"]);
+        assert_eq!(
+            map.to_string(),
+            "1 ## ##\n2 .# ..\n3 .. .#\n4 .. ..\n5 .. ..\n6 .. ..\n"
+        );
+    }
+}
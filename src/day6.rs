@@ -1,57 +1,189 @@
-use std::collections::HashMap;
+use std::fmt;
 
-#[aoc_generator(day6)]
-fn generator_input(input: &str) -> Vec<String> {
-    input
-        .split("\n\n")
-        .map(|s| s.replace("\n", ","))
-        .collect::<Vec<_>>()
+use crate::records::groups;
+
+pub mod cli;
+
+#[derive(Debug, PartialEq)]
+struct AnswerError {
+    line: usize,
+    column: usize,
+    found: char,
 }
 
-#[aoc(day6, part1)]
-fn part1(input: &[String]) -> i32 {
-    input
-        .iter()
-        .map(|f| {
-            let mut chars = f
-                .chars()
-                .filter(|p| match p {
-                    ',' => false,
-                    _ => true,
-                })
-                .collect::<Vec<_>>();
-            chars.sort();
-            chars.dedup();
-            chars.len() as i32
-        })
-        .sum()
+impl fmt::Display for AnswerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected a question from 'a' to 'z', found {:?}",
+            self.line, self.column, self.found
+        )
+    }
 }
 
-#[aoc(day6, part2)]
-fn part2(input: &[String]) -> i32 {
-    input
-        .iter()
-        .map(|f| {
-            let answers = f.split(',').collect::<Vec<_>>();
-            let mut yesses = HashMap::new();
-            let mut member_count = 0;
-            for answer in answers {
-                member_count += 1;
-                for choice in answer.chars() {
-                    *yesses.entry(choice).or_insert(0) += 1;
+impl std::error::Error for AnswerError {}
+
+// The questions one person answered "yes" to, question 'a' being bit 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Answers(u32);
+
+impl Answers {
+    const ALL: Answers = Answers((1 << 26) - 1);
+
+    fn parse(line: &str) -> Result<Self, usize> {
+        line.chars()
+            .enumerate()
+            .try_fold(Answers(0), |set, (i, c)| {
+                if c.is_ascii_lowercase() {
+                    Ok(Answers(set.0 | 1 << (c as u8 - b'a')))
+                } else {
+                    Err(i)
                 }
+            })
+    }
+
+    fn contains(self, question: char) -> bool {
+        question.is_ascii_lowercase() && self.0 >> (question as u8 - b'a') & 1 == 1
+    }
+
+    fn union(self, other: Answers) -> Answers {
+        Answers(self.0 | other.0)
+    }
+
+    fn intersection(self, other: Answers) -> Answers {
+        Answers(self.0 & other.0)
+    }
+
+    fn symmetric_difference(self, other: Answers) -> Answers {
+        Answers(self.0 ^ other.0)
+    }
+
+    fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    fn questions(self) -> impl Iterator<Item = char> {
+        (b'a'..=b'z')
+            .map(char::from)
+            .filter(move |&q| self.contains(q))
+    }
+}
+
+impl fmt::Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.questions().try_for_each(|q| write!(f, "{}", q))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Group {
+    members: Vec<Answers>,
+}
+
+impl Group {
+    fn union(&self) -> Answers {
+        self.members.iter().fold(Answers(0), |acc, &m| acc.union(m))
+    }
+
+    fn intersection(&self) -> Answers {
+        self.members
+            .iter()
+            .fold(Answers::ALL, |acc, &m| acc.intersection(m))
+    }
+
+    // Questions answered by an odd number of members.
+    fn symmetric_difference(&self) -> Answers {
+        self.members
+            .iter()
+            .fold(Answers(0), |acc, &m| acc.symmetric_difference(m))
+    }
+
+    fn counts(&self) -> [usize; 26] {
+        let mut counts = [0; 26];
+        for member in &self.members {
+            for q in member.questions() {
+                counts[(q as u8 - b'a') as usize] += 1;
             }
-            yesses
+        }
+        counts
+    }
+
+    fn answered_by_at_least(&self, k: usize) -> Answers {
+        self.counts()
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count >= k)
+            .fold(Answers(0), |acc, (i, _)| Answers(acc.0 | 1 << i))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct QuestionFrequency {
+    question: char,
+    // Number of people that answered the question, over all groups.
+    people: usize,
+    // Number of groups in which at least one member answered it.
+    groups: usize,
+    // Number of groups in which every member answered it.
+    unanimous: usize,
+}
+
+fn frequency_report(groups: &[Group]) -> Vec<QuestionFrequency> {
+    (b'a'..=b'z')
+        .map(char::from)
+        .map(|question| QuestionFrequency {
+            question,
+            people: groups
+                .iter()
+                .map(|g| g.members.iter().filter(|m| m.contains(question)).count())
+                .sum(),
+            groups: groups
+                .iter()
+                .filter(|g| g.union().contains(question))
+                .count(),
+            unanimous: groups
+                .iter()
+                .filter(|g| g.intersection().contains(question))
+                .count(),
+        })
+        .collect()
+}
+
+#[aoc_generator(day6)]
+fn generator_input(input: &str) -> Result<Vec<Group>, AnswerError> {
+    groups(input)
+        .iter()
+        .map(|group| {
+            let members = group
+                .lines
                 .iter()
-                .filter(|(_, &count)| count == member_count)
-                .count() as i32
+                .enumerate()
+                .map(|(offset, line)| {
+                    Answers::parse(line).map_err(|i| AnswerError {
+                        line: group.first_line + offset,
+                        column: i + 1,
+                        found: line.chars().nth(i).unwrap(),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Group { members })
         })
-        .sum()
+        .collect()
+}
+
+#[aoc(day6, part1)]
+fn part1(input: &[Group]) -> usize {
+    input.iter().map(|group| group.union().len()).sum()
+}
+
+#[aoc(day6, part2)]
+fn part2(input: &[Group]) -> usize {
+    input.iter().map(|group| group.intersection().len()).sum()
 }
 
 #[cfg(test)]
 pub mod tests {
-    use super::{generator_input, part1, part2};
+    use super::{frequency_report, generator_input, part1, part2, AnswerError, QuestionFrequency};
     static INPUT_RAW: &str = r#"abc
 
 a
@@ -70,19 +202,71 @@ b"#;
 
     #[test]
     fn generator() {
-        let input = generator_input(&INPUT_RAW);
-        assert_eq!(input, vec!["abc", "a,b,c", "ab,ac", "a,a,a,a", "b"]);
+        let input = generator_input(INPUT_RAW).unwrap();
+        let members = input
+            .iter()
+            .map(|g| g.members.iter().map(|m| m.to_string()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            members,
+            vec![
+                vec!["abc"],
+                vec!["a", "b", "c"],
+                vec!["ab", "ac"],
+                vec!["a", "a", "a", "a"],
+                vec!["b"],
+            ]
+        );
+        assert_eq!(
+            generator_input("ab\n\nca\ncB").unwrap_err(),
+            AnswerError {
+                line: 4,
+                column: 2,
+                found: 'B'
+            }
+        );
+    }
+
+    #[test]
+    fn set_queries() {
+        let input = generator_input("abcx\nabcy\nabcz\nax").unwrap();
+        let group = &input[0];
+        assert_eq!(group.union().to_string(), "abcxyz");
+        assert_eq!(group.intersection().to_string(), "a");
+        assert_eq!(group.symmetric_difference().to_string(), "bcyz");
+        assert_eq!(group.answered_by_at_least(2).to_string(), "abcx");
+        assert_eq!(group.answered_by_at_least(1), group.union());
+        assert_eq!(group.answered_by_at_least(4), group.intersection());
+        assert_eq!(group.answered_by_at_least(5).len(), 0);
+    }
+
+    #[test]
+    fn frequencies() {
+        let input = generator_input(INPUT_RAW).unwrap();
+        let report = frequency_report(&input);
+        assert_eq!(report.len(), 26);
+        assert_eq!(
+            report[0],
+            QuestionFrequency {
+                question: 'a',
+                people: 8,
+                groups: 4,
+                unanimous: 3
+            }
+        );
+        assert_eq!(report[2].people, 3);
+        assert_eq!(report[25].groups, 0);
     }
 
     #[test]
     fn day6_part1() {
-        let input = generator_input(&INPUT_RAW);
+        let input = generator_input(&INPUT_RAW).unwrap();
         assert_eq!(part1(&input), 11);
     }
 
     #[test]
     fn day6_part2() {
-        let input = generator_input(&INPUT_RAW);
+        let input = generator_input(&INPUT_RAW).unwrap();
         assert_eq!(part2(&input), 6);
     }
 }
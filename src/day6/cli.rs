@@ -0,0 +1,101 @@
+use super::{frequency_report, generator_input, Answers, Group};
+
+pub const USAGE: &str = "usage: customs [--input FILE] COMMAND
+commands, each printing the questions of every group and how many there are in all:
+    any           questions anyone in the group answered
+    all           questions everyone in the group answered
+    odd           questions an odd number of the group answered
+    at-least K    questions at least K members of the group answered
+    frequencies   per question, how many people, groups and unanimous groups answered it";
+
+fn per_group(groups: &[Group], query: impl Fn(&Group) -> Answers) -> String {
+    let mut output = String::new();
+    let mut total = 0;
+    for (i, group) in groups.iter().enumerate() {
+        let answers = query(group);
+        total += answers.len();
+        output.push_str(&format!("group {}: {}\n", i + 1, answers));
+    }
+    output.push_str(&format!("total: {}\n", total));
+    output
+}
+
+// Runs one command against the answers in `input` and returns what it prints, or the usage
+// problem or parse error as a message. `args` does not include the program name or the input file
+// option.
+pub fn run(args: &[String], input: &str) -> Result<String, String> {
+    let groups = generator_input(input).map_err(|e| e.to_string())?;
+    let args = args.iter().map(|a| a.as_str()).collect::<Vec<_>>();
+    match args.as_slice() {
+        ["any"] => Ok(per_group(&groups, Group::union)),
+        ["all"] => Ok(per_group(&groups, Group::intersection)),
+        ["odd"] => Ok(per_group(&groups, Group::symmetric_difference)),
+        ["at-least", k] => {
+            let k = k
+                .parse()
+                .map_err(|_| format!("invalid member count '{}'\n{}", k, USAGE))?;
+            Ok(per_group(&groups, |group| group.answered_by_at_least(k)))
+        }
+        ["frequencies"] => Ok(frequency_report(&groups)
+            .iter()
+            .map(|f| {
+                format!(
+                    "{}: {} people, {} groups, {} unanimous\n",
+                    f.question, f.people, f.groups, f.unanimous
+                )
+            })
+            .collect()),
+        [] => Err(format!("missing command\n{}", USAGE)),
+        [command, ..] => Err(format!(
+            "unknown command or wrong arguments for '{}'\n{}",
+            command, USAGE
+        )),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::run;
+
+    fn run_args(args: &[&str], input: &str) -> Result<String, String> {
+        let args = args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        run(&args, input)
+    }
+
+    #[test]
+    fn group_queries() {
+        let input = "abcx\nabcy\nabcz\nax\n\nb";
+        assert_eq!(
+            run_args(&["any"], input).unwrap(),
+            "group 1: abcxyz\ngroup 2: b\ntotal: 7\n"
+        );
+        assert_eq!(
+            run_args(&["all"], input).unwrap(),
+            "group 1: a\ngroup 2: b\ntotal: 2\n"
+        );
+        assert_eq!(
+            run_args(&["odd"], input).unwrap(),
+            "group 1: bcyz\ngroup 2: b\ntotal: 5\n"
+        );
+        assert_eq!(
+            run_args(&["at-least", "2"], input).unwrap(),
+            "group 1: abcx\ngroup 2: \ntotal: 4\n"
+        );
+        assert!(run_args(&["at-least", "two"], input)
+            .unwrap_err()
+            .starts_with("invalid member count 'two'\nusage:"));
+        assert_eq!(
+            run_args(&["any"], "ab\n\nca\ncB").unwrap_err(),
+            "line 4, column 2: expected a question from 'a' to 'z', found 'B'"
+        );
+    }
+
+    #[test]
+    fn frequencies() {
+        let output = run_args(&["frequencies"], "ab\nb\n\nb").unwrap();
+        assert!(output.starts_with(
+            "a: 1 people, 1 groups, 0 unanimous\nb: 3 people, 2 groups, 2 unanimous\n"
+        ));
+        assert_eq!(output.lines().count(), 26);
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt;
+
+use super::graph::{BagGraph, GraphError};
+use super::parser::RuleError;
+use super::{generator_input, query, DEFAULT_ROOT};
+
+pub const USAGE: &str = "usage: bags [--input FILE] COMMAND
+commands:
+    containers [COLOR]     bags that can eventually contain COLOR
+    count [COLOR]          number of bags inside COLOR
+    paths FROM TO          every chain of bags from FROM down to TO
+    deepest [COLOR]        the longest chain of nested bags, starting at COLOR if given
+    dot [COLOR [RADIUS]]   Graphviz export of all rules, or of the rules around COLOR
+    cycle                  a chain of bags that ends up inside itself, if the rules have one
+COLOR defaults to \"shiny gold\" where it is optional, RADIUS to 1.";
+
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    Usage(String),
+    Rules(RuleError),
+    Graph(GraphError),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{}\n{}", message, USAGE),
+            CliError::Rules(e) => write!(f, "{}", e),
+            CliError::Graph(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<RuleError> for CliError {
+    fn from(e: RuleError) -> Self {
+        CliError::Rules(e)
+    }
+}
+
+impl From<GraphError> for CliError {
+    fn from(e: GraphError) -> Self {
+        CliError::Graph(e)
+    }
+}
+
+fn lines(values: Vec<String>) -> String {
+    values.iter().map(|v| format!("{}\n", v)).collect()
+}
+
+// Runs one command against the rules in `input` and returns what it prints. `args` does not
+// include the program name or the input file option.
+pub fn run(args: &[String], input: &str) -> Result<String, CliError> {
+    let graph = BagGraph::from_rules(&generator_input(input)?)?;
+    let args = args.iter().map(|a| a.as_str()).collect::<Vec<_>>();
+    let color = |i: usize| args.get(i).copied().unwrap_or(DEFAULT_ROOT);
+    match args.as_slice() {
+        ["containers"] | ["containers", _] => Ok(lines(query::containers_of(&graph, color(1))?)),
+        ["count"] | ["count", _] => Ok(format!("{}\n", query::bags_inside(&graph, color(1))?)),
+        ["paths", from, to] => Ok(query::paths(&graph, from, to)?
+            .into_iter()
+            .map(|path| format!("{}\n", path.join(" > ")))
+            .collect()),
+        ["deepest"] => Ok(lines(query::deepest_chain(&graph, None)?)),
+        ["deepest", root] => Ok(lines(query::deepest_chain(&graph, Some(root))?)),
+        ["dot"] => Ok(query::to_dot(&graph, None)?),
+        ["dot", center] => Ok(query::to_dot(&graph, Some((center, 1)))?),
+        ["dot", center, radius] => {
+            let radius = radius
+                .parse()
+                .map_err(|_| CliError::Usage(format!("invalid radius '{}'", radius)))?;
+            Ok(query::to_dot(&graph, Some((center, radius)))?)
+        }
+        ["cycle"] => Ok(match graph.find_cycle() {
+            Some(cycle) => format!("{}\n", cycle.join(" > ")),
+            None => "no cycle\n".to_string(),
+        }),
+        [] => Err(CliError::Usage("missing command".to_string())),
+        [command, ..] => Err(CliError::Usage(format!(
+            "unknown command or wrong arguments for '{}'",
+            command
+        ))),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{run, CliError};
+    use crate::day7::graph::GraphError;
+    use crate::day7::tests::INPUT_RAW;
+
+    fn run_args(args: &[&str]) -> Result<String, CliError> {
+        let args = args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        run(&args, INPUT_RAW)
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(
+            run_args(&["containers"]).unwrap(),
+            "bright white\ndark orange\nlight red\nmuted yellow\n"
+        );
+        assert_eq!(run_args(&["count", "vibrant plum"]).unwrap(), "11\n");
+        assert_eq!(
+            run_args(&["paths", "dark orange", "shiny gold"]).unwrap(),
+            "dark orange > bright white > shiny gold\ndark orange > muted yellow > shiny gold\n"
+        );
+        assert_eq!(
+            run_args(&["deepest", "shiny gold"]).unwrap(),
+            "shiny gold\ndark olive\ndotted black\n"
+        );
+        assert!(run_args(&["dot", "faded blue", "0"])
+            .unwrap()
+            .contains("\"faded blue\" [style=bold];\n}"));
+        assert_eq!(run_args(&["cycle"]).unwrap(), "no cycle\n");
+        assert_eq!(
+            run(
+                &["cycle".to_string()],
+                "a b bags contain 1 c d bag.\nc d bags contain 2 a b bags."
+            )
+            .unwrap(),
+            "a b > c d > a b\n"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            run_args(&[]),
+            Err(CliError::Usage("missing command".to_string()))
+        );
+        assert!(matches!(
+            run_args(&["paths", "light red"]),
+            Err(CliError::Usage(_))
+        ));
+        assert!(matches!(
+            run_args(&["dot", "shiny gold", "x"]),
+            Err(CliError::Usage(_))
+        ));
+        assert_eq!(
+            run_args(&["count", "plaid purple"]),
+            Err(CliError::Graph(GraphError::UnknownColor(
+                "plaid purple".to_string()
+            )))
+        );
+        assert!(matches!(
+            run(
+                &["count".to_string()],
+                "shiny gold bags contain 1 gold bag."
+            ),
+            Err(CliError::Rules(_))
+        ));
+    }
+}
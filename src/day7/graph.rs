@@ -0,0 +1,329 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::fmt;
+
+use super::MapColorContainsOthers;
+
+pub type BagId = usize;
+
+#[derive(Debug, PartialEq)]
+pub enum GraphError {
+    UnknownColor(String),
+    InvalidCount {
+        container: String,
+        color: String,
+        count: i32,
+    },
+    // The colors along the cycle, starting and ending with the same color.
+    Cycle(Vec<String>),
+    Overflow(String),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphError::UnknownColor(color) => write!(f, "unknown bag color '{}'", color),
+            GraphError::InvalidCount {
+                container,
+                color,
+                count,
+            } => write!(
+                f,
+                "{} bags cannot contain {} {} bags",
+                container, count, color
+            ),
+            GraphError::Cycle(colors) => {
+                write!(f, "bags contain themselves: {}", colors.join(" -> "))
+            }
+            GraphError::Overflow(color) => write!(f, "too many bags inside {}", color),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+// Colors are interned in sorted order, so ids do not depend on the input's hash map order.
+#[derive(Debug)]
+pub struct BagGraph {
+    names: Vec<String>,
+    ids: HashMap<String, BagId>,
+    // For each bag, the bags directly inside it and how many of them.
+    contents: Vec<Vec<(BagId, u64)>>,
+    // For each bag, the bags that directly contain it.
+    containers: Vec<Vec<BagId>>,
+}
+
+impl BagGraph {
+    pub fn from_rules(rules: &MapColorContainsOthers) -> Result<Self, GraphError> {
+        let names = rules
+            .iter()
+            .flat_map(|(color, inside)| Some(color).into_iter().chain(inside.keys()))
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let ids = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (name.clone(), id))
+            .collect::<HashMap<_, _>>();
+
+        let mut contents = vec![vec![]; names.len()];
+        let mut containers = vec![vec![]; names.len()];
+        for (color, inside) in rules {
+            let outer = ids[color];
+            for (inner_color, &count) in inside {
+                let weight = u64::try_from(count).map_err(|_| GraphError::InvalidCount {
+                    container: color.clone(),
+                    color: inner_color.clone(),
+                    count,
+                })?;
+                let inner = ids[inner_color];
+                contents[outer].push((inner, weight));
+                containers[inner].push(outer);
+            }
+        }
+        for edges in contents.iter_mut() {
+            edges.sort_unstable();
+        }
+        for edges in containers.iter_mut() {
+            edges.sort_unstable();
+        }
+        Ok(BagGraph {
+            names,
+            ids,
+            contents,
+            containers,
+        })
+    }
+
+    pub fn id(&self, color: &str) -> Result<BagId, GraphError> {
+        self.ids
+            .get(color)
+            .copied()
+            .ok_or_else(|| GraphError::UnknownColor(color.to_string()))
+    }
+
+    pub fn bag_count(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn name(&self, id: BagId) -> &str {
+        &self.names[id]
+    }
+
+    pub fn contents(&self, id: BagId) -> &[(BagId, u64)] {
+        &self.contents[id]
+    }
+
+    pub fn containers(&self, id: BagId) -> &[BagId] {
+        &self.containers[id]
+    }
+
+    pub fn names_of(&self, ids: Vec<BagId>) -> Vec<String> {
+        ids.into_iter().map(|id| self.names[id].clone()).collect()
+    }
+
+    // Depth-first post order of everything reachable from `roots`, children before their
+    // containers. Iterative, so deep nesting cannot overflow the stack. Fails with the first cycle
+    // found, as a list of ids whose first and last entry are the same bag.
+    pub fn post_order(
+        &self,
+        roots: impl IntoIterator<Item = BagId>,
+    ) -> Result<Vec<BagId>, Vec<BagId>> {
+        const UNSEEN: u8 = 0;
+        const ON_STACK: u8 = 1;
+        const DONE: u8 = 2;
+        let mut state = vec![UNSEEN; self.names.len()];
+        let mut order = vec![];
+        for root in roots {
+            if state[root] != UNSEEN {
+                continue;
+            }
+            state[root] = ON_STACK;
+            let mut stack = vec![(root, 0)];
+            while let Some((bag, next)) = stack.last_mut() {
+                let bag = *bag;
+                match self.contents[bag].get(*next) {
+                    Some(&(inner, _)) => {
+                        *next += 1;
+                        match state[inner] {
+                            UNSEEN => {
+                                state[inner] = ON_STACK;
+                                stack.push((inner, 0));
+                            }
+                            ON_STACK => {
+                                let start = stack.iter().position(|&(b, _)| b == inner).unwrap();
+                                let mut cycle =
+                                    stack[start..].iter().map(|&(b, _)| b).collect::<Vec<_>>();
+                                cycle.push(inner);
+                                return Err(cycle);
+                            }
+                            _ => {}
+                        }
+                    }
+                    None => {
+                        state[bag] = DONE;
+                        order.push(bag);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        Ok(order)
+    }
+
+    pub fn find_cycle(&self) -> Option<Vec<String>> {
+        self.post_order(0..self.names.len())
+            .err()
+            .map(|cycle| self.names_of(cycle))
+    }
+
+    // Every bag that can eventually contain `id`, found breadth first over the reverse edges.
+    pub fn ancestors(&self, id: BagId) -> BTreeSet<BagId> {
+        let mut seen = BTreeSet::new();
+        let mut queue = self.containers[id].iter().copied().collect::<VecDeque<_>>();
+        while let Some(bag) = queue.pop_front() {
+            if seen.insert(bag) {
+                queue.extend(self.containers[bag].iter().copied());
+            }
+        }
+        seen
+    }
+
+    // The number of bags inside each bag reachable from `id`, memoized so that every bag is only
+    // counted once however many paths lead to it.
+    pub fn descendant_counts(&self, id: BagId) -> Result<HashMap<BagId, u64>, GraphError> {
+        let order = self
+            .post_order(Some(id))
+            .map_err(|cycle| GraphError::Cycle(self.names_of(cycle)))?;
+        let mut counts: HashMap<BagId, u64> = HashMap::new();
+        for bag in order {
+            let count = self.contents[bag]
+                .iter()
+                .try_fold(0u64, |total, &(inner, weight)| {
+                    counts[&inner]
+                        .checked_add(1)
+                        .and_then(|bags| weight.checked_mul(bags))
+                        .and_then(|bags| total.checked_add(bags))
+                })
+                .ok_or_else(|| GraphError::Overflow(self.names[bag].clone()))?;
+            counts.insert(bag, count);
+        }
+        Ok(counts)
+    }
+
+    pub fn descendant_count(&self, id: BagId) -> Result<u64, GraphError> {
+        Ok(self.descendant_counts(id)?[&id])
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::collections::HashMap;
+
+    use super::{BagGraph, GraphError};
+    use crate::day7::MapColorContainsOthers;
+
+    fn rules(rules: &[(&str, &[(&str, i32)])]) -> MapColorContainsOthers {
+        rules
+            .iter()
+            .map(|(color, inside)| {
+                (
+                    color.to_string(),
+                    inside
+                        .iter()
+                        .map(|(c, n)| (c.to_string(), *n))
+                        .collect::<HashMap<_, _>>(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reports_cycles() {
+        let graph = BagGraph::from_rules(&rules(&[
+            ("a", &[("b", 1)]),
+            ("b", &[("c", 2), ("d", 1)]),
+            ("c", &[("a", 1)]),
+        ]))
+        .unwrap();
+        let cycle = vec!["a", "b", "c", "a"]
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        assert_eq!(graph.find_cycle(), Some(cycle.clone()));
+        assert_eq!(
+            graph.descendant_count(graph.id("b").unwrap()),
+            Err(GraphError::Cycle(
+                vec!["b", "c", "a", "b"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect()
+            ))
+        );
+        assert_eq!(
+            GraphError::Cycle(cycle).to_string(),
+            "bags contain themselves: a -> b -> c -> a"
+        );
+        assert_eq!(graph.descendant_count(graph.id("d").unwrap()), Ok(0));
+        assert_eq!(graph.ancestors(graph.id("d").unwrap()).len(), 3);
+    }
+
+    #[test]
+    fn deep_lattice() {
+        // Every bag holds one of each bag on the next level, so a bag on level l is reachable
+        // along 2^l paths.
+        let levels = 60;
+        let names = (0..=levels)
+            .flat_map(|l| vec![format!("left {}", l), format!("right {}", l)])
+            .collect::<Vec<_>>();
+        let mut map = HashMap::new();
+        for l in 0..levels {
+            let inside = names[2 * l + 2..2 * l + 4]
+                .iter()
+                .map(|n| (n.clone(), 1))
+                .collect::<HashMap<_, _>>();
+            map.insert(names[2 * l].clone(), inside.clone());
+            map.insert(names[2 * l + 1].clone(), inside);
+        }
+        let graph = BagGraph::from_rules(&map).unwrap();
+        assert_eq!(graph.find_cycle(), None);
+        // Level l holds 2 + 4 + ... + 2^(levels - l) bags.
+        assert_eq!(
+            graph.descendant_count(graph.id("left 10").unwrap()),
+            Ok((1 << 51) - 2)
+        );
+        assert_eq!(
+            graph.descendant_count(graph.id("left 0").unwrap()),
+            Ok((1 << 61) - 2)
+        );
+        assert_eq!(graph.ancestors(graph.id("right 60").unwrap()).len(), 120);
+    }
+
+    #[test]
+    fn checked_counts() {
+        let graph = BagGraph::from_rules(&rules(&[
+            ("a", &[("b", i32::MAX)]),
+            ("b", &[("c", i32::MAX)]),
+            ("c", &[("d", i32::MAX)]),
+        ]))
+        .unwrap();
+        assert_eq!(
+            graph.descendant_count(graph.id("a").unwrap()),
+            Err(GraphError::Overflow("a".to_string()))
+        );
+        assert_eq!(
+            BagGraph::from_rules(&rules(&[("a", &[("b", -1)])])).unwrap_err(),
+            GraphError::InvalidCount {
+                container: "a".to_string(),
+                color: "b".to_string(),
+                count: -1
+            }
+        );
+        assert_eq!(
+            graph.id("x"),
+            Err(GraphError::UnknownColor("x".to_string()))
+        );
+    }
+}
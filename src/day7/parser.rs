@@ -0,0 +1,484 @@
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Content {
+    pub count: i64,
+    pub color: String,
+    // Position of the count.
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub color: String,
+    pub line: usize,
+    pub column: usize,
+    pub contents: Vec<Content>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RuleErrorKind {
+    Expected {
+        expected: &'static str,
+        found: Option<String>,
+    },
+    InvalidCount(i64),
+    // Counts are stored as i32. Kept as written, since it may not even fit in an i64.
+    CountTooLarge(String),
+    DuplicateRule {
+        color: String,
+        first_line: usize,
+    },
+    // The same color listed twice in one rule.
+    DuplicateContent {
+        color: String,
+        first_column: usize,
+    },
+    UndefinedColor(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RuleError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: RuleErrorKind,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            RuleErrorKind::Expected {
+                expected,
+                found: Some(found),
+            } => write!(f, "expected {}, found '{}'", expected, found),
+            RuleErrorKind::Expected {
+                expected,
+                found: None,
+            } => write!(f, "expected {}, found end of line", expected),
+            RuleErrorKind::InvalidCount(count) => {
+                write!(
+                    f,
+                    "a bag must contain at least one of each bag, not {}",
+                    count
+                )
+            }
+            RuleErrorKind::CountTooLarge(count) => {
+                write!(f, "{} bags are more than can be counted", count)
+            }
+            RuleErrorKind::DuplicateRule { color, first_line } => write!(
+                f,
+                "{} bags are already defined at line {}",
+                color, first_line
+            ),
+            RuleErrorKind::DuplicateContent {
+                color,
+                first_column,
+            } => write!(
+                f,
+                "{} bags are already listed at column {}",
+                color, first_column
+            ),
+            RuleErrorKind::UndefinedColor(color) => write!(f, "no rule for {} bags", color),
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    Comma,
+    Period,
+}
+
+// Splits a line into words, commas and periods, each with its 1-based column.
+fn tokenize(line: &str) -> Vec<(usize, Token<'_>)> {
+    let mut tokens = vec![];
+    let mut word_start = None;
+    for (column, (i, c)) in line
+        .char_indices()
+        .chain(Some((line.len(), ' ')))
+        .enumerate()
+    {
+        if c.is_whitespace() || c == ',' || c == '.' {
+            if let Some((start_column, start)) = word_start.take() {
+                tokens.push((start_column + 1, Token::Word(&line[start..i])));
+            }
+            match c {
+                ',' => tokens.push((column + 1, Token::Comma)),
+                '.' => tokens.push((column + 1, Token::Period)),
+                _ => {}
+            }
+        } else if word_start.is_none() {
+            word_start = Some((column, i));
+        }
+    }
+    tokens
+}
+
+// An optionally signed run of digits, whether or not it fits in an i64.
+fn is_count(word: &str) -> bool {
+    let digits = word.strip_prefix(['-', '+']).unwrap_or(word);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+// A cursor over the tokens of one line:
+//   rule     = color "bags" "contain" contents "."
+//   contents = "no" "other" "bags" | item { "," item }
+//   item     = count color ( "bag" | "bags" )
+//   color    = word { word }
+struct LineParser<'a> {
+    line: usize,
+    end_column: usize,
+    tokens: Vec<(usize, Token<'a>)>,
+    position: usize,
+}
+
+impl<'a> LineParser<'a> {
+    fn new(line: usize, input: &'a str) -> Self {
+        LineParser {
+            line,
+            end_column: input.chars().count() + 1,
+            tokens: tokenize(input),
+            position: 0,
+        }
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map_or(self.end_column, |&(column, _)| column)
+    }
+
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn peek_word(&self) -> Option<&'a str> {
+        match self.peek() {
+            Some(&Token::Word(word)) => Some(word),
+            _ => None,
+        }
+    }
+
+    fn peek_words(&self, words: &[&str]) -> bool {
+        words.iter().enumerate().all(|(i, &word)| {
+            self.tokens.get(self.position + i).map(|(_, token)| token) == Some(&Token::Word(word))
+        })
+    }
+
+    fn error(&self, expected: &'static str) -> RuleError {
+        let found = self.peek().map(|token| match token {
+            Token::Word(word) => word.to_string(),
+            Token::Comma => ",".to_string(),
+            Token::Period => ".".to_string(),
+        });
+        RuleError {
+            line: self.line,
+            column: self.column(),
+            kind: RuleErrorKind::Expected { expected, found },
+        }
+    }
+
+    fn expect(&mut self, token: Token, expected: &'static str) -> Result<(), RuleError> {
+        if self.peek() == Some(&token) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
+    }
+
+    // The words up to and including "bag" or "bags".
+    fn color(&mut self) -> Result<String, RuleError> {
+        let mut words = vec![];
+        loop {
+            match self.peek_word() {
+                Some("bag") | Some("bags") if !words.is_empty() => {
+                    self.position += 1;
+                    return Ok(words.join(" "));
+                }
+                Some(word) if word != "bag" && word != "bags" && !is_count(word) => {
+                    words.push(word);
+                    self.position += 1;
+                }
+                _ if words.is_empty() => return Err(self.error("a color")),
+                _ => return Err(self.error("'bags'")),
+            }
+        }
+    }
+
+    fn item(&mut self) -> Result<Content, RuleError> {
+        let (line, column) = (self.line, self.column());
+        let word = self
+            .peek_word()
+            .filter(|&word| is_count(word))
+            .ok_or_else(|| self.error("a count or 'no other bags'"))?;
+        let count = word.parse::<i64>().map_err(|_| RuleError {
+            line,
+            column,
+            kind: RuleErrorKind::CountTooLarge(word.to_string()),
+        })?;
+        self.position += 1;
+        Ok(Content {
+            count,
+            color: self.color()?,
+            line,
+            column,
+        })
+    }
+
+    fn rule(mut self) -> Result<Rule, RuleError> {
+        let column = self.column();
+        let color = self.color()?;
+        self.expect(Token::Word("contain"), "'contain'")?;
+        let mut contents = vec![];
+        if self.peek_words(&["no", "other", "bags"]) {
+            self.position += 3;
+        } else {
+            contents.push(self.item()?);
+            while self.peek() == Some(&Token::Comma) {
+                self.position += 1;
+                contents.push(self.item()?);
+            }
+        }
+        self.expect(Token::Period, "',' or '.'")?;
+        if self.peek().is_some() {
+            return Err(self.error("end of line"));
+        }
+        Ok(Rule {
+            color,
+            line: self.line,
+            column,
+            contents,
+        })
+    }
+}
+
+// Parses one rule per non-blank line, stopping at the first syntax error.
+pub fn parse_rules(input: &str) -> Result<Vec<Rule>, RuleError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| LineParser::new(i + 1, line).rule())
+        .collect()
+}
+
+// Every problem with otherwise well-formed rules, in input order.
+pub fn validate(rules: &[Rule]) -> Vec<RuleError> {
+    let mut errors = vec![];
+    let mut defined = HashMap::new();
+    for rule in rules {
+        if let Some(&first_line) = defined.get(rule.color.as_str()) {
+            errors.push(RuleError {
+                line: rule.line,
+                column: rule.column,
+                kind: RuleErrorKind::DuplicateRule {
+                    color: rule.color.clone(),
+                    first_line,
+                },
+            });
+        } else {
+            defined.insert(rule.color.as_str(), rule.line);
+        }
+    }
+    for rule in rules {
+        let mut listed = HashMap::new();
+        for content in &rule.contents {
+            let error = |kind| RuleError {
+                line: content.line,
+                column: content.column,
+                kind,
+            };
+            if content.count < 1 {
+                errors.push(error(RuleErrorKind::InvalidCount(content.count)));
+            } else if content.count > i32::MAX as i64 {
+                errors.push(error(RuleErrorKind::CountTooLarge(
+                    content.count.to_string(),
+                )));
+            }
+            if let Some(&first_column) = listed.get(content.color.as_str()) {
+                errors.push(error(RuleErrorKind::DuplicateContent {
+                    color: content.color.clone(),
+                    first_column,
+                }));
+            } else {
+                listed.insert(content.color.as_str(), content.column);
+            }
+            if !defined.contains_key(content.color.as_str()) {
+                errors.push(error(RuleErrorKind::UndefinedColor(content.color.clone())));
+            }
+        }
+    }
+    errors.sort_by_key(|e| (e.line, e.column));
+    errors
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{parse_rules, validate, Content, Rule, RuleErrorKind};
+
+    fn error(input: &str) -> String {
+        parse_rules(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_any_color_length() {
+        let rules = parse_rules(
+            "teal bags contain 1 very dark mauve bag, 12 teal bags.\n\n\
+             very dark mauve bags contain no other bags.",
+        )
+        .unwrap();
+        assert_eq!(
+            rules[0],
+            Rule {
+                color: "teal".to_string(),
+                line: 1,
+                column: 1,
+                contents: vec![
+                    Content {
+                        count: 1,
+                        color: "very dark mauve".to_string(),
+                        line: 1,
+                        column: 19,
+                    },
+                    Content {
+                        count: 12,
+                        color: "teal".to_string(),
+                        line: 1,
+                        column: 42,
+                    },
+                ],
+            }
+        );
+        assert_eq!(rules[1].color, "very dark mauve");
+        assert_eq!(rules[1].line, 3);
+        assert!(rules[1].contents.is_empty());
+    }
+
+    #[test]
+    fn positioned_syntax_errors() {
+        assert_eq!(
+            error("light red bags hold 1 bright white bag."),
+            "line 1, column 16: expected 'contain', found 'hold'"
+        );
+        assert_eq!(
+            error("a b bags contain 1 c bag.\nd bags contain 2 e bags"),
+            "line 2, column 24: expected ',' or '.', found end of line"
+        );
+        assert_eq!(
+            error("a bags contain 1 bags."),
+            "line 1, column 18: expected a color, found 'bags'"
+        );
+        assert_eq!(
+            error("a bags contain b bags."),
+            "line 1, column 16: expected a count or 'no other bags', found 'b'"
+        );
+        assert_eq!(
+            error("a bags contain no other bags. b"),
+            "line 1, column 31: expected end of line, found 'b'"
+        );
+        assert_eq!(
+            error("a bags contain 1 b."),
+            "line 1, column 19: expected 'bags', found '.'"
+        );
+        assert_eq!(
+            error("a bags contain no b bags."),
+            "line 1, column 16: expected a count or 'no other bags', found 'no'"
+        );
+        assert_eq!(
+            error("a bags contain no other bag."),
+            "line 1, column 16: expected a count or 'no other bags', found 'no'"
+        );
+    }
+
+    #[test]
+    fn counts_beyond_i64() {
+        let err = parse_rules("a bags contain 2 b bags, 99999999999999999999 b bags.").unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.kind),
+            (
+                1,
+                26,
+                RuleErrorKind::CountTooLarge("99999999999999999999".to_string())
+            )
+        );
+        assert_eq!(
+            error("a bags contain 99999999999999999999 b bags."),
+            "line 1, column 16: 99999999999999999999 bags are more than can be counted"
+        );
+    }
+
+    #[test]
+    fn colors_starting_with_no() {
+        let rules = parse_rules(
+            "a bags contain 2 no other bags, 1 no way bag.
+             no other bags contain no other bags.
+             no way bags contain no other bags.",
+        )
+        .unwrap();
+        let contents = rules[0]
+            .contents
+            .iter()
+            .map(|c| (c.count, c.color.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(contents, vec![(2, "no other"), (1, "no way")]);
+        assert_eq!(rules[1].color, "no other");
+        assert!(rules[1].contents.is_empty());
+        assert!(validate(&rules).is_empty());
+    }
+
+    #[test]
+    fn validation() {
+        let rules = parse_rules(
+            "a bags contain 1 b bag, 0 c bags.\n\
+             b bags contain -2 a bags.\n\
+             a bags contain no other bags.",
+        )
+        .unwrap();
+        let errors = validate(&rules);
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| (e.line, e.column))
+                .collect::<Vec<_>>(),
+            vec![(1, 25), (1, 25), (2, 16), (3, 1)]
+        );
+        assert_eq!(errors[0].kind, RuleErrorKind::InvalidCount(0));
+        assert_eq!(
+            errors[1].kind,
+            RuleErrorKind::UndefinedColor("c".to_string())
+        );
+        assert_eq!(
+            errors[3].to_string(),
+            "line 3, column 1: a bags are already defined at line 1"
+        );
+        assert_eq!(
+            errors[2].to_string(),
+            "line 2, column 16: a bag must contain at least one of each bag, not -2"
+        );
+
+        let rules = parse_rules(
+            "a bags contain 2 b bags, 3000000000 b bags.
+             b bags contain no other bags.",
+        )
+        .unwrap();
+        let errors = validate(&rules);
+        assert_eq!(
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            vec![
+                "line 1, column 26: 3000000000 bags are more than can be counted",
+                "line 1, column 26: b bags are already listed at column 16",
+            ]
+        );
+        assert_eq!(
+            errors[0].kind,
+            RuleErrorKind::CountTooLarge("3000000000".to_string())
+        );
+    }
+}
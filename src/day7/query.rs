@@ -0,0 +1,233 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::Write;
+
+use super::graph::{BagGraph, BagId, GraphError};
+
+// Every bag that can eventually contain `color`, sorted by color.
+pub fn containers_of(graph: &BagGraph, color: &str) -> Result<Vec<String>, GraphError> {
+    let ancestors = graph.ancestors(graph.id(color)?);
+    Ok(graph.names_of(ancestors.into_iter().collect()))
+}
+
+pub fn bags_inside(graph: &BagGraph, color: &str) -> Result<u64, GraphError> {
+    graph.descendant_count(graph.id(color)?)
+}
+
+// Every chain of bags from `from` down to `to`, each one directly containing the next. Bags that
+// cannot reach `to` are never entered, and no chain visits a bag twice.
+pub fn paths(graph: &BagGraph, from: &str, to: &str) -> Result<Vec<Vec<String>>, GraphError> {
+    let (from, to) = (graph.id(from)?, graph.id(to)?);
+    let mut relevant = graph.ancestors(to);
+    relevant.insert(to);
+
+    let mut paths = vec![];
+    let mut path = vec![from];
+    let mut on_path = HashSet::new();
+    on_path.insert(from);
+    // Each stack entry is the index of the next child to try for the bag at the same depth.
+    let mut next = vec![0];
+    while let Some(i) = next.last_mut() {
+        let bag = *path.last().unwrap();
+        if bag == to {
+            paths.push(graph.names_of(path.clone()));
+        } else if let Some(&(inner, _)) = graph.contents(bag).get(*i) {
+            *i += 1;
+            if relevant.contains(&inner) && on_path.insert(inner) {
+                path.push(inner);
+                next.push(0);
+            }
+            continue;
+        }
+        on_path.remove(&bag);
+        path.pop();
+        next.pop();
+    }
+    Ok(paths)
+}
+
+// The longest chain of directly nested bags starting at `root`, or anywhere if there is no root.
+// Ties go to the chain that comes first by color.
+pub fn deepest_chain(graph: &BagGraph, root: Option<&str>) -> Result<Vec<String>, GraphError> {
+    let roots = match root {
+        Some(color) => vec![graph.id(color)?],
+        None => (0..graph.bag_count()).collect(),
+    };
+    let order = graph
+        .post_order(roots.iter().copied())
+        .map_err(|cycle| GraphError::Cycle(graph.names_of(cycle)))?;
+
+    // For each bag, the length of the longest chain below it and the bag it continues with.
+    let mut depth = vec![(0, None); graph.bag_count()];
+    for bag in order {
+        depth[bag] = graph
+            .contents(bag)
+            .iter()
+            .map(|&(inner, _)| (depth[inner].0 + 1, Some(inner)))
+            .fold((1, None), |best, candidate| {
+                if candidate.0 > best.0 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+    }
+    let mut bag = match roots
+        .iter()
+        .copied()
+        .max_by_key(|&r| (depth[r].0, -(r as i64)))
+    {
+        Some(bag) => bag,
+        None => return Ok(vec![]),
+    };
+    let mut chain = vec![bag];
+    while let Some(inner) = depth[bag].1 {
+        chain.push(inner);
+        bag = inner;
+    }
+    Ok(graph.names_of(chain))
+}
+
+// The bags at most `radius` containment steps above or below `center`.
+fn neighbourhood(graph: &BagGraph, center: BagId, radius: usize) -> BTreeSet<BagId> {
+    let mut seen = BTreeSet::new();
+    seen.insert(center);
+    let mut frontier = vec![center];
+    for _ in 0..radius {
+        frontier = frontier
+            .iter()
+            .flat_map(|&bag| {
+                graph
+                    .contents(bag)
+                    .iter()
+                    .map(|&(inner, _)| inner)
+                    .chain(graph.containers(bag).iter().copied())
+            })
+            .filter(|&bag| seen.insert(bag))
+            .collect();
+    }
+    seen
+}
+
+// Graphviz export of all rules, or of the rules within `radius` steps of one color. Edges point
+// from the outer bag to the inner one and are labelled with the count.
+pub fn to_dot(graph: &BagGraph, around: Option<(&str, usize)>) -> Result<String, GraphError> {
+    let (bags, center) = match around {
+        Some((color, radius)) => {
+            let center = graph.id(color)?;
+            (neighbourhood(graph, center, radius), Some(center))
+        }
+        None => ((0..graph.bag_count()).collect(), None),
+    };
+    let mut dot = String::from("digraph bags {\n");
+    for &bag in &bags {
+        let style = if Some(bag) == center {
+            " [style=bold]"
+        } else {
+            ""
+        };
+        writeln!(dot, "    {:?}{};", graph.name(bag), style).unwrap();
+    }
+    for &bag in &bags {
+        for &(inner, count) in graph.contents(bag) {
+            if bags.contains(&inner) {
+                writeln!(
+                    dot,
+                    "    {:?} -> {:?} [label=\"{}\"];",
+                    graph.name(bag),
+                    graph.name(inner),
+                    count
+                )
+                .unwrap();
+            }
+        }
+    }
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{bags_inside, containers_of, deepest_chain, paths, to_dot};
+    use crate::day7::generator_input;
+    use crate::day7::graph::BagGraph;
+    use crate::day7::tests::INPUT_RAW;
+
+    fn graph() -> BagGraph {
+        BagGraph::from_rules(&generator_input(INPUT_RAW).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn containment_queries() {
+        let graph = graph();
+        assert_eq!(
+            containers_of(&graph, "muted yellow").unwrap(),
+            vec!["dark orange", "light red"]
+        );
+        assert_eq!(bags_inside(&graph, "dark olive"), Ok(7));
+        assert_eq!(bags_inside(&graph, "faded blue"), Ok(0));
+        assert!(containers_of(&graph, "plaid purple").is_err());
+    }
+
+    #[test]
+    fn all_paths() {
+        let graph = graph();
+        assert_eq!(
+            paths(&graph, "light red", "shiny gold").unwrap(),
+            vec![
+                vec!["light red", "bright white", "shiny gold"],
+                vec!["light red", "muted yellow", "shiny gold"],
+            ]
+        );
+        assert_eq!(paths(&graph, "light red", "faded blue").unwrap().len(), 5);
+        assert_eq!(
+            paths(&graph, "faded blue", "faded blue").unwrap(),
+            vec![vec!["faded blue"]]
+        );
+        assert!(paths(&graph, "shiny gold", "light red").unwrap().is_empty());
+    }
+
+    #[test]
+    fn deepest() {
+        let graph = graph();
+        assert_eq!(
+            deepest_chain(&graph, None).unwrap(),
+            vec![
+                "dark orange",
+                "bright white",
+                "shiny gold",
+                "dark olive",
+                "dotted black"
+            ]
+        );
+        assert_eq!(
+            deepest_chain(&graph, Some("muted yellow")).unwrap(),
+            vec!["muted yellow", "shiny gold", "dark olive", "dotted black"]
+        );
+    }
+
+    #[test]
+    fn dot_export() {
+        let graph = graph();
+        let dot = to_dot(&graph, None).unwrap();
+        assert!(dot.starts_with("digraph bags {\n    \"bright white\";\n"));
+        assert!(dot.contains("    \"light red\" -> \"muted yellow\" [label=\"2\"];\n"));
+        assert_eq!(dot.lines().count(), 2 + 9 + 13);
+
+        let around = to_dot(&graph, Some(("shiny gold", 1))).unwrap();
+        assert_eq!(
+            around,
+            r#"digraph bags {
+    "bright white";
+    "dark olive";
+    "muted yellow";
+    "shiny gold" [style=bold];
+    "vibrant plum";
+    "bright white" -> "shiny gold" [label="1"];
+    "muted yellow" -> "shiny gold" [label="2"];
+    "shiny gold" -> "dark olive" [label="1"];
+    "shiny gold" -> "vibrant plum" [label="2"];
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use super::vm::{Instruction, InstructionSet, Word};
+use super::{flipped, next_pc};
+
+// Where a run starting at some instruction ends up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Terminates,
+    OutOfBounds(Word),
+    // Index into `Analysis::cycles`.
+    Loops(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flip {
+    pub index: usize,
+    pub from: &'static str,
+    pub to: &'static str,
+}
+
+// Control flow of a program with the standard operations, worked out without running it. Every
+// instruction has exactly one successor, so each run either leaves the program or ends up going
+// round one cycle forever.
+#[derive(Debug)]
+pub struct Analysis {
+    outcomes: Vec<Outcome>,
+    // Each cycle starting at its lowest instruction, in the order the run goes round it.
+    pub cycles: Vec<Vec<usize>>,
+}
+
+impl Analysis {
+    pub fn of(program: &[Instruction]) -> Self {
+        let len = program.len();
+        let mut outcomes: Vec<Option<Outcome>> = vec![None; len];
+        let mut on_path = vec![false; len];
+        let mut cycles = vec![];
+        for start in 0..len {
+            let mut path = vec![];
+            let mut pc = start as Word;
+            let outcome = loop {
+                if pc == len as Word {
+                    break Outcome::Terminates;
+                }
+                if pc < 0 || pc > len as Word {
+                    break Outcome::OutOfBounds(pc);
+                }
+                let index = pc as usize;
+                if let Some(outcome) = outcomes[index] {
+                    break outcome;
+                }
+                if on_path[index] {
+                    let first = path.iter().position(|&p| p == index).unwrap();
+                    let mut cycle = path[first..].to_vec();
+                    let lowest = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap();
+                    cycle.rotate_left(lowest);
+                    cycles.push(cycle);
+                    break Outcome::Loops(cycles.len() - 1);
+                }
+                on_path[index] = true;
+                path.push(index);
+                pc = next_pc(&program[index], index);
+            };
+            for index in path {
+                on_path[index] = false;
+                outcomes[index] = Some(outcome);
+            }
+        }
+        Analysis {
+            outcomes: outcomes.into_iter().map(Option::unwrap).collect(),
+            cycles,
+        }
+    }
+
+    // Where a run starting at `pc` ends up.
+    pub fn outcome(&self, pc: Word) -> Outcome {
+        match pc {
+            pc if pc == self.outcomes.len() as Word => Outcome::Terminates,
+            pc if 0 <= pc && pc < self.outcomes.len() as Word => self.outcomes[pc as usize],
+            pc => Outcome::OutOfBounds(pc),
+        }
+    }
+
+    // What happens to the whole program.
+    pub fn verdict(&self) -> Outcome {
+        self.outcome(0)
+    }
+
+    // The cycle a run starting at `pc` gets stuck in, if it does.
+    pub fn cycle_of(&self, pc: Word) -> Option<&[usize]> {
+        match self.outcome(pc) {
+            Outcome::Loops(cycle) => Some(&self.cycles[cycle]),
+            _ => None,
+        }
+    }
+
+    // Every single jmp/nop flip after which the program terminates. Flipping an instruction the
+    // run never reaches changes nothing, so those only count if the program terminates already.
+    pub fn terminating_flips(&self, program: &[Instruction]) -> Vec<Flip> {
+        let len = program.len();
+        let mut on_run = vec![false; len];
+        let mut pc = 0;
+        while 0 <= pc && pc < len as Word && !on_run[pc as usize] {
+            on_run[pc as usize] = true;
+            pc = next_pc(&program[pc as usize], pc as usize);
+        }
+        let terminates = self.verdict() == Outcome::Terminates;
+        let order = self.terminating_tree(program);
+
+        let set = InstructionSet::standard();
+        (0..len)
+            .filter_map(|index| {
+                let flip = flipped(&program[index], &set)?;
+                let helps = if !on_run[index] {
+                    terminates
+                } else {
+                    let target = next_pc(&flip, index);
+                    // The new target must terminate without coming back through the flipped
+                    // instruction, that is without `index` being on the way from it to the end.
+                    self.outcome(target) == Outcome::Terminates
+                        && match (order[index], order.get(target as usize).copied().flatten()) {
+                            (Some((enter, exit)), Some((t, _))) => !(enter <= t && t < exit),
+                            _ => true,
+                        }
+                };
+                Some(Flip {
+                    index,
+                    from: program[index].mnemonic(),
+                    to: flip.mnemonic(),
+                })
+                .filter(|_| helps)
+            })
+            .collect()
+    }
+
+    // The instructions that terminate form a tree rooted one past the end, with each instruction's
+    // successor as its parent. Numbers them depth first so that `a` lies on the way from `b` to the
+    // end exactly when `b`'s number falls within `a`'s (enter, exit) range.
+    fn terminating_tree(&self, program: &[Instruction]) -> Vec<Option<(usize, usize)>> {
+        let len = program.len();
+        let mut children = vec![vec![]; len + 1];
+        for (pc, instruction) in program.iter().enumerate() {
+            if self.outcomes[pc] == Outcome::Terminates {
+                children[next_pc(instruction, pc) as usize].push(pc);
+            }
+        }
+        let mut order = vec![None; len + 1];
+        let mut counter = 0;
+        // Each frame is a node and how many of its children have been visited.
+        let mut frames = vec![(len, 0)];
+        order[len] = Some((0, 0));
+        while let Some(&mut (node, ref mut visited)) = frames.last_mut() {
+            if let Some(&child) = children[node].get(*visited) {
+                *visited += 1;
+                counter += 1;
+                order[child] = Some((counter, 0));
+                frames.push((child, 0));
+            } else {
+                frames.pop();
+                if let Some((_, exit)) = order[node].as_mut() {
+                    *exit = counter + 1;
+                }
+            }
+        }
+        order
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{Analysis, Flip, Outcome};
+    use crate::day8::repair::repairs_brute_force;
+    use crate::day8::repair::tests::random_program;
+    use crate::day8::tests::INPUT_RAW;
+    use crate::day8::vm::{ExitReason, InstructionSet, Vm};
+
+    #[test]
+    fn sample() {
+        let program = InstructionSet::standard().parse(INPUT_RAW).unwrap();
+        let analysis = Analysis::of(&program);
+        assert_eq!(analysis.verdict(), Outcome::Loops(0));
+        assert_eq!(analysis.cycle_of(0), Some(&[1, 2, 6, 7, 3, 4][..]));
+        assert_eq!(analysis.cycle_of(5), analysis.cycle_of(0));
+        assert_eq!(analysis.outcome(8), Outcome::Terminates);
+        assert_eq!(analysis.outcome(-1), Outcome::OutOfBounds(-1));
+        assert_eq!(
+            analysis.terminating_flips(&program),
+            vec![Flip {
+                index: 7,
+                from: "jmp",
+                to: "nop",
+            }]
+        );
+    }
+
+    #[test]
+    fn classifications() {
+        let analyse = |input: &str| {
+            let program = InstructionSet::standard().parse(input).unwrap();
+            let analysis = Analysis::of(&program);
+            let flips = analysis
+                .terminating_flips(&program)
+                .iter()
+                .map(|flip| flip.index)
+                .collect::<Vec<_>>();
+            (analysis.verdict(), flips)
+        };
+        assert_eq!(
+            analyse("nop +0\njmp +2\nacc +1\nnop +0"),
+            (Outcome::Terminates, vec![1])
+        );
+        assert_eq!(
+            analyse("acc +1\njmp -3\nnop +0"),
+            (Outcome::OutOfBounds(-2), vec![1])
+        );
+        assert_eq!(
+            analyse("nop +2\njmp +0\nacc +1"),
+            (Outcome::Loops(0), vec![0, 1])
+        );
+        // Flipping the second nop jumps back onto the run, which then goes round forever.
+        assert_eq!(analyse("nop +1\nnop -1"), (Outcome::Terminates, vec![0]));
+        assert_eq!(analyse(""), (Outcome::Terminates, vec![]));
+    }
+
+    #[test]
+    fn agrees_with_running() {
+        for seed in 0..300 {
+            let program = random_program(seed, 40);
+            let analysis = Analysis::of(&program);
+            match Vm::new(&program).run() {
+                ExitReason::Halted => assert_eq!(analysis.verdict(), Outcome::Terminates),
+                ExitReason::OutOfBounds { pc } => {
+                    assert_eq!(analysis.verdict(), Outcome::OutOfBounds(pc))
+                }
+                ExitReason::Looping { pc } => {
+                    assert!(analysis.cycle_of(0).unwrap().contains(&pc), "seed {}", seed)
+                }
+                ExitReason::BudgetExhausted => unreachable!(),
+            }
+            let expected = repairs_brute_force(&program)
+                .iter()
+                .map(|repair| repair.index)
+                .collect::<Vec<_>>();
+            let flips = analysis
+                .terminating_flips(&program)
+                .iter()
+                .map(|flip| flip.index)
+                .collect::<Vec<_>>();
+            assert_eq!(flips, expected, "seed {}", seed);
+        }
+    }
+}
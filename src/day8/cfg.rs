@@ -0,0 +1,308 @@
+use std::fmt::Write;
+
+use super::next_pc;
+use super::vm::{Instruction, Word};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeKind {
+    Jump,
+    Fallthrough,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    Block(usize),
+    // Just past the last instruction, where the program terminates.
+    Exit,
+    OutOfBounds(Word),
+}
+
+// Every block ends in exactly one way: with a jump or by falling into the next instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    pub start: usize,
+    // Exclusive.
+    pub end: usize,
+    pub acc_delta: Word,
+    pub kind: EdgeKind,
+    pub target: Target,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Cfg {
+    pub blocks: Vec<BasicBlock>,
+}
+
+impl Cfg {
+    pub fn build(program: &[Instruction]) -> Self {
+        let len = program.len();
+        let mut leader = vec![false; len + 1];
+        leader[0] = true;
+        for (pc, instruction) in program.iter().enumerate() {
+            if instruction.mnemonic() == "jmp" {
+                leader[pc + 1] = true;
+                let target = next_pc(instruction, pc);
+                if 0 <= target && target < len as Word {
+                    leader[target as usize] = true;
+                }
+            }
+        }
+        let starts = (0..len).filter(|&pc| leader[pc]).collect::<Vec<_>>();
+
+        let blocks = starts
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = starts.get(i + 1).copied().unwrap_or(len);
+                let last = &program[end - 1];
+                let kind = if last.mnemonic() == "jmp" {
+                    EdgeKind::Jump
+                } else {
+                    EdgeKind::Fallthrough
+                };
+                let target = next_pc(last, end - 1);
+                BasicBlock {
+                    start,
+                    end,
+                    acc_delta: program[start..end]
+                        .iter()
+                        .filter(|instruction| instruction.mnemonic() == "acc")
+                        .map(|instruction| instruction.arg)
+                        .fold(0, Word::wrapping_add),
+                    kind,
+                    // Every jump target in range starts a block.
+                    target: match target {
+                        pc if pc == len as Word => Target::Exit,
+                        pc if 0 <= pc && pc < len as Word => {
+                            Target::Block(starts.binary_search(&(pc as usize)).unwrap())
+                        }
+                        pc => Target::OutOfBounds(pc),
+                    },
+                }
+            })
+            .collect();
+        Cfg { blocks }
+    }
+
+    pub fn block_of(&self, pc: usize) -> Option<usize> {
+        self.blocks
+            .iter()
+            .position(|block| block.start <= pc && pc < block.end)
+    }
+
+    fn successor(&self, block: usize) -> Option<usize> {
+        match self.blocks[block].target {
+            Target::Block(next) => Some(next),
+            _ => None,
+        }
+    }
+
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut block = Some(0).filter(|_| !self.blocks.is_empty());
+        while let Some(b) = block.filter(|&b| !reachable[b]) {
+            reachable[b] = true;
+            block = self.successor(b);
+        }
+        reachable
+    }
+
+    pub fn unreachable_blocks(&self) -> Vec<usize> {
+        self.reachable()
+            .iter()
+            .enumerate()
+            .filter(|(_, &reachable)| !reachable)
+            .map(|(block, _)| block)
+            .collect()
+    }
+
+    // Strongly connected components with more than one block or a block jumping to itself, found
+    // with an iterative version of Tarjan's algorithm. Each loop lists its blocks in order.
+    pub fn loops(&self) -> Vec<Vec<usize>> {
+        let n = self.blocks.len();
+        let mut index = vec![None; n];
+        let mut low = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = vec![];
+        let mut next_index = 0;
+        let mut components = vec![];
+
+        for root in 0..n {
+            if index[root].is_some() {
+                continue;
+            }
+            // Each frame is a block and whether its successor has been visited yet.
+            let mut frames = vec![(root, false)];
+            while let Some(&mut (block, ref mut done)) = frames.last_mut() {
+                if index[block].is_none() {
+                    index[block] = Some(next_index);
+                    low[block] = next_index;
+                    next_index += 1;
+                    stack.push(block);
+                    on_stack[block] = true;
+                }
+                if !*done {
+                    *done = true;
+                    if let Some(next) = self.successor(block) {
+                        match index[next] {
+                            None => {
+                                frames.push((next, false));
+                                continue;
+                            }
+                            Some(i) if on_stack[next] => low[block] = low[block].min(i),
+                            _ => {}
+                        }
+                    }
+                    continue;
+                }
+                frames.pop();
+                if let Some(&(parent, _)) = frames.last() {
+                    low[parent] = low[parent].min(low[block]);
+                }
+                if Some(low[block]) == index[block] {
+                    let mut component = vec![];
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component.push(member);
+                        if member == block {
+                            break;
+                        }
+                    }
+                    if component.len() > 1 || self.successor(block) == Some(block) {
+                        component.sort_unstable();
+                        components.push(component);
+                    }
+                }
+            }
+        }
+        components.sort();
+        components
+    }
+
+    // Graphviz export with one box per block, listing its instructions and accumulator delta.
+    // Unreachable blocks are grey, blocks on a loop red.
+    pub fn to_dot(&self, program: &[Instruction]) -> String {
+        let reachable = self.reachable();
+        let in_loop = self.loops().concat();
+        let mut dot = String::from("digraph program {\n    node [shape=box];\n");
+        for (i, block) in self.blocks.iter().enumerate() {
+            let mut label = format!("acc {:+}\\l", block.acc_delta);
+            for (pc, instruction) in (block.start..).zip(&program[block.start..block.end]) {
+                write!(label, "{}: {}\\l", pc, instruction).unwrap();
+            }
+            let color = if !reachable[i] {
+                ", color=grey, fontcolor=grey"
+            } else if in_loop.contains(&i) {
+                ", color=red"
+            } else {
+                ""
+            };
+            writeln!(dot, "    b{} [label=\"{}\"{}];", i, label, color).unwrap();
+        }
+        let mut out_of_bounds = vec![];
+        for (i, block) in self.blocks.iter().enumerate() {
+            let target = match block.target {
+                Target::Block(next) => format!("b{}", next),
+                Target::Exit => "exit".to_string(),
+                Target::OutOfBounds(pc) => {
+                    out_of_bounds.push(pc);
+                    format!("\"pc {}\"", pc)
+                }
+            };
+            let style = match block.kind {
+                EdgeKind::Jump => "",
+                EdgeKind::Fallthrough => " [style=dashed]",
+            };
+            writeln!(dot, "    b{} -> {}{};", i, target, style).unwrap();
+        }
+        if self.blocks.iter().any(|block| block.target == Target::Exit) {
+            dot.push_str("    exit [shape=doublecircle];\n");
+        }
+        out_of_bounds.sort_unstable();
+        out_of_bounds.dedup();
+        for pc in out_of_bounds {
+            writeln!(dot, "    \"pc {}\" [shape=octagon];", pc).unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{BasicBlock, Cfg, EdgeKind, Target};
+    use crate::day8::vm::InstructionSet;
+
+    static INPUT_RAW: &str =
+        "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6";
+
+    #[test]
+    fn basic_blocks() {
+        let program = InstructionSet::standard().parse(INPUT_RAW).unwrap();
+        let cfg = Cfg::build(&program);
+        let summary = cfg
+            .blocks
+            .iter()
+            .map(|b| (b.start, b.end, b.acc_delta, b.target))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (0, 1, 0, Target::Block(1)),
+                (1, 3, 1, Target::Block(4)),
+                (3, 5, 3, Target::Block(1)),
+                (5, 6, -99, Target::Block(4)),
+                (6, 8, 1, Target::Block(2)),
+                (8, 9, 6, Target::Exit),
+            ]
+        );
+        assert_eq!(cfg.blocks[0].kind, EdgeKind::Fallthrough);
+        assert_eq!(cfg.blocks[1].kind, EdgeKind::Jump);
+        assert_eq!(cfg.block_of(7), Some(4));
+        assert_eq!(cfg.unreachable_blocks(), vec![3, 5]);
+        assert_eq!(cfg.loops(), vec![vec![1, 2, 4]]);
+    }
+
+    #[test]
+    fn self_loops_and_out_of_bounds() {
+        let program = InstructionSet::standard()
+            .parse("acc +1\njmp +0\njmp -5\njmp +2\nnop +0")
+            .unwrap();
+        let cfg = Cfg::build(&program);
+        assert_eq!(cfg.loops(), vec![vec![1]]);
+        assert_eq!(
+            cfg.blocks[2],
+            BasicBlock {
+                start: 2,
+                end: 3,
+                acc_delta: 0,
+                kind: EdgeKind::Jump,
+                target: Target::OutOfBounds(-3),
+            }
+        );
+        assert_eq!(cfg.blocks[3].target, Target::Exit);
+        assert_eq!(cfg.unreachable_blocks(), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn dot_export() {
+        let program = InstructionSet::standard()
+            .parse("acc +2\njmp +2\njmp -3\nacc -1")
+            .unwrap();
+        assert_eq!(
+            Cfg::build(&program).to_dot(&program),
+            r#"digraph program {
+    node [shape=box];
+    b0 [label="acc +2\l0: acc +2\l1: jmp +2\l"];
+    b1 [label="acc +0\l2: jmp -3\l", color=grey, fontcolor=grey];
+    b2 [label="acc -1\l3: acc -1\l"];
+    b0 -> b2;
+    b1 -> "pc -1";
+    b2 -> exit [style=dashed];
+    exit [shape=doublecircle];
+    "pc -1" [shape=octagon];
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,552 @@
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, Write};
+
+use super::analysis::{Analysis, Outcome};
+use super::cfg::{Cfg, EdgeKind, Target};
+use super::repair::repairs_brute_force;
+use super::vm::{ExitReason, Instruction, ParseError, TraceEntry, Vm, Word};
+
+pub const HELP: &str = "commands:
+    break LINE | break acc VALUE    stop before LINE runs or once the accumulator becomes VALUE
+    delete [LINE | acc VALUE]       remove one breakpoint, or all of them
+    breakpoints                     list the breakpoints
+    step [N]                        run N instructions, 1 by default
+    continue                        run at least one instruction, then until a breakpoint is hit
+                                    or the program stops
+    back [N]                        undo the last N instructions, 1 by default
+    trace [N]                       show the last N executed instructions, 10 by default
+    loop                            show the instructions of the detected loop
+    state                           show the next instruction and the accumulator
+    repairs                         list every jmp/nop flip that makes the program terminate
+    blocks                          list the basic blocks, where they go and which never run
+    dot                             show the control flow graph in Graphviz format
+    analyze                         show where the program and the current line end up without
+                                    running them, and which flips make the program terminate
+    help                            show this message
+    quit
+b, d, s, c, r, t and p are short for break, delete, step, continue, back, trace and state.";
+
+pub fn parse(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    super::generator_input(input)
+}
+
+// Lines are 1-based like in the program source, the program counter is 0-based.
+fn line(pc: Word) -> Word {
+    pc.saturating_add(1)
+}
+
+fn describe(entry: &TraceEntry) -> String {
+    format!(
+        "line {}: {} | acc {}\n",
+        line(entry.pc as Word),
+        entry.instruction,
+        entry.acc
+    )
+}
+
+pub struct Debugger<'a> {
+    program: &'a [Instruction],
+    vm: Vm<'a>,
+    lines: BTreeSet<usize>,
+    values: BTreeSet<Word>,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(program: &'a [Instruction]) -> Self {
+        Debugger {
+            program,
+            vm: Vm::new(program).with_trace(),
+            lines: BTreeSet::new(),
+            values: BTreeSet::new(),
+        }
+    }
+
+    fn exit_message(&self, exit: ExitReason) -> String {
+        let acc = self.vm.state.acc();
+        match exit {
+            ExitReason::Halted => format!("program halted with acc {}\n", acc),
+            ExitReason::Looping { pc } => format!(
+                "loop detected: line {} would run a second time, acc is {}\n",
+                line(pc as Word),
+                acc
+            ),
+            ExitReason::OutOfBounds { pc } => format!(
+                "jumped to line {}, outside the program, acc is {}\n",
+                line(pc),
+                acc
+            ),
+            ExitReason::BudgetExhausted => format!("step budget exhausted, acc is {}\n", acc),
+        }
+    }
+
+    // Runs one instruction, appending what happened to `out`. Returns whether the machine can go
+    // on.
+    fn step(&mut self, out: &mut String) -> bool {
+        let executed = self.vm.trace().len();
+        let exit = self.vm.step();
+        if self.vm.trace().len() > executed {
+            out.push_str(&describe(self.vm.trace().last().unwrap()));
+        }
+        match exit {
+            Some(exit) => {
+                out.push_str(&self.exit_message(exit));
+                false
+            }
+            None => true,
+        }
+    }
+
+    fn breaks_at(&self, pc: Word) -> bool {
+        usize::try_from(pc).is_ok_and(|pc| self.lines.contains(&(pc + 1)))
+    }
+
+    // Runs until the machine stops or a breakpoint is hit, showing only the last instruction.
+    // Breakpoints are only checked after each instruction, so a breakpoint on the current line
+    // does not keep it from moving on.
+    fn run_on(&mut self) -> String {
+        let executed = self.vm.trace().len();
+        let stop = loop {
+            let acc = self.vm.state.acc();
+            if let Some(exit) = self.vm.step() {
+                break self.exit_message(exit);
+            }
+            let pc = self.vm.state.pc;
+            if self.breaks_at(pc) {
+                break format!("breakpoint at line {}\n", line(pc));
+            }
+            let now = self.vm.state.acc();
+            if now != acc && self.values.contains(&now) {
+                break format!("breakpoint: acc is {}\n", now);
+            }
+        };
+        let last = self.vm.trace()[executed..]
+            .last()
+            .map(describe)
+            .unwrap_or_default();
+        last + &stop
+    }
+
+    fn state(&self) -> String {
+        let pc = self.vm.state.pc;
+        let next = usize::try_from(pc).ok().and_then(|pc| self.program.get(pc));
+        match next {
+            Some(instruction) => format!(
+                "next line {}: {} | acc {} | {} steps\n",
+                line(pc),
+                instruction,
+                self.vm.state.acc(),
+                self.vm.steps
+            ),
+            None => format!(
+                "at line {}, outside the program | acc {} | {} steps\n",
+                line(pc),
+                self.vm.state.acc(),
+                self.vm.steps
+            ),
+        }
+    }
+
+    fn blocks(&self) -> String {
+        let cfg = Cfg::build(self.program);
+        let unreachable = cfg.unreachable_blocks();
+        let in_loop = cfg.loops().concat();
+        let current = usize::try_from(self.vm.state.pc)
+            .ok()
+            .and_then(|pc| cfg.block_of(pc));
+        let mut out = String::new();
+        for (i, block) in cfg.blocks.iter().enumerate() {
+            let edge = match block.kind {
+                EdgeKind::Jump => "jumps",
+                EdgeKind::Fallthrough => "falls through",
+            };
+            let target = match block.target {
+                Target::Block(next) => format!("block {}", next),
+                Target::Exit => "the end".to_string(),
+                Target::OutOfBounds(pc) => format!("line {}, outside the program", line(pc)),
+            };
+            write!(
+                out,
+                "block {}: lines {}-{} | acc {:+} | {} to {}",
+                i,
+                block.start + 1,
+                block.end,
+                block.acc_delta,
+                edge,
+                target
+            )
+            .unwrap();
+            if unreachable.contains(&i) {
+                out.push_str(" | unreachable");
+            }
+            if in_loop.contains(&i) {
+                out.push_str(" | in a loop");
+            }
+            if current == Some(i) {
+                out.push_str(" | current");
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn analyze(&self) -> String {
+        let analysis = Analysis::of(self.program);
+        let ending = |pc: Word| match analysis.outcome(pc) {
+            Outcome::Terminates => "terminates".to_string(),
+            Outcome::OutOfBounds(pc) => format!("jumps to line {}, outside the program", line(pc)),
+            Outcome::Loops(_) => {
+                let lines = analysis.cycle_of(pc).unwrap().iter();
+                let lines = lines.map(|&pc| line(pc as Word).to_string());
+                format!("loops over lines {}", lines.collect::<Vec<_>>().join(", "))
+            }
+        };
+        let mut out = format!("the program {}\n", ending(0));
+        let pc = self.vm.state.pc;
+        if pc != 0 {
+            writeln!(out, "from line {} it {}", line(pc), ending(pc)).unwrap();
+        }
+        let flips = analysis.terminating_flips(self.program);
+        if analysis.verdict() != Outcome::Terminates && flips.is_empty() {
+            out.push_str("no single flip makes it terminate\n");
+        }
+        for flip in flips {
+            writeln!(
+                out,
+                "flipping line {} from {} to {} makes it terminate",
+                flip.index + 1,
+                flip.from,
+                flip.to
+            )
+            .unwrap();
+        }
+        out
+    }
+
+    fn breakpoint(&self, args: &[&str]) -> Result<Breakpoint, String> {
+        match args {
+            ["acc", value] => value
+                .parse()
+                .map(Breakpoint::Acc)
+                .map_err(|_| format!("invalid accumulator value '{}'", value)),
+            [number] => match number.parse() {
+                Ok(number) if 1 <= number && number <= self.program.len() => {
+                    Ok(Breakpoint::Line(number))
+                }
+                Ok(number) => Err(format!(
+                    "line {} is not in the program, which has {} lines",
+                    number,
+                    self.program.len()
+                )),
+                Err(_) => Err(format!("invalid line '{}'", number)),
+            },
+            _ => Err("expected LINE or acc VALUE".to_string()),
+        }
+    }
+
+    fn count(args: &[&str], default: usize) -> Result<usize, String> {
+        match args {
+            [] => Ok(default),
+            [n] => n.parse().map_err(|_| format!("invalid count '{}'", n)),
+            _ => Err("expected at most one count".to_string()),
+        }
+    }
+
+    // Runs one command line and returns what it prints, or `None` to quit.
+    pub fn execute(&mut self, command: &str) -> Option<String> {
+        let words = command.split_whitespace().collect::<Vec<_>>();
+        let result = match words.as_slice() {
+            [] => Ok(String::new()),
+            ["quit"] | ["q"] => return None,
+            ["help"] => Ok(format!("{}\n", HELP)),
+            ["break", args @ ..] | ["b", args @ ..] => {
+                self.breakpoint(args).map(|breakpoint| match breakpoint {
+                    Breakpoint::Line(number) => {
+                        self.lines.insert(number);
+                        format!("breakpoint at line {}\n", number)
+                    }
+                    Breakpoint::Acc(value) => {
+                        self.values.insert(value);
+                        format!("breakpoint when acc becomes {}\n", value)
+                    }
+                })
+            }
+            ["delete"] | ["d"] => {
+                self.lines.clear();
+                self.values.clear();
+                Ok("deleted all breakpoints\n".to_string())
+            }
+            ["delete", args @ ..] | ["d", args @ ..] => {
+                self.breakpoint(args).and_then(|breakpoint| {
+                    let removed = match breakpoint {
+                        Breakpoint::Line(number) => self.lines.remove(&number),
+                        Breakpoint::Acc(value) => self.values.remove(&value),
+                    };
+                    if removed {
+                        Ok("deleted\n".to_string())
+                    } else {
+                        Err("no such breakpoint".to_string())
+                    }
+                })
+            }
+            ["breakpoints"] => Ok(self
+                .lines
+                .iter()
+                .map(|number| format!("line {}\n", number))
+                .chain(self.values.iter().map(|value| format!("acc {}\n", value)))
+                .collect()),
+            ["step", args @ ..] | ["s", args @ ..] => Self::count(args, 1).map(|n| {
+                let mut out = String::new();
+                for _ in 0..n {
+                    if !self.step(&mut out) {
+                        break;
+                    }
+                }
+                out
+            }),
+            ["continue"] | ["c"] => Ok(self.run_on()),
+            ["back", args @ ..] | ["r", args @ ..] => Self::count(args, 1).map(|n| {
+                let mut out = String::new();
+                for _ in 0..n {
+                    match self.vm.step_back() {
+                        Some(entry) => write!(out, "undid {}", describe(&entry)).unwrap(),
+                        None => {
+                            out.push_str("at the start of the program\n");
+                            break;
+                        }
+                    }
+                }
+                out + &self.state()
+            }),
+            ["trace", args @ ..] | ["t", args @ ..] => Self::count(args, 10).map(|n| {
+                let trace = self.vm.trace();
+                trace[trace.len().saturating_sub(n)..]
+                    .iter()
+                    .map(describe)
+                    .collect()
+            }),
+            ["loop"] => Ok(match self.vm.loop_trace() {
+                Some(entries) => entries.iter().map(describe).collect(),
+                None => "no loop detected yet\n".to_string(),
+            }),
+            ["state"] | ["p"] => Ok(self.state()),
+            ["blocks"] => Ok(self.blocks()),
+            ["dot"] => Ok(Cfg::build(self.program).to_dot(self.program)),
+            ["analyze"] => Ok(self.analyze()),
+            ["repairs"] => Ok(match repairs_brute_force(self.program).as_slice() {
+                [] => "no single flip makes the program terminate\n".to_string(),
+                repairs => repairs
+                    .iter()
+                    .map(|repair| {
+                        format!(
+                            "line {}: {} -> {}, acc {}\n",
+                            repair.index + 1,
+                            repair.from,
+                            repair.to,
+                            repair.acc
+                        )
+                    })
+                    .collect(),
+            }),
+            [command, ..] => Err(format!("unknown command '{}', try 'help'", command)),
+        };
+        Some(result.unwrap_or_else(|e| format!("error: {}\n", e)))
+    }
+}
+
+enum Breakpoint {
+    Line(usize),
+    Acc(Word),
+}
+
+// Reads commands from `input` until it ends or says quit, prompting on `output`.
+pub fn run(
+    program: &[Instruction],
+    mut input: impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let mut debugger = Debugger::new(program);
+    let mut command = String::new();
+    loop {
+        write!(output, "(dbg) ")?;
+        output.flush()?;
+        command.clear();
+        if input.read_line(&mut command)? == 0 {
+            return Ok(());
+        }
+        match debugger.execute(&command) {
+            Some(out) => write!(output, "{}", out)?,
+            None => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{parse, run, Debugger};
+    use crate::day8::tests::INPUT_RAW;
+
+    #[test]
+    fn breakpoints_and_loop() {
+        let program = parse(INPUT_RAW).unwrap();
+        let mut debugger = Debugger::new(&program);
+        let mut execute = |command: &str| debugger.execute(command).unwrap();
+        assert_eq!(
+            execute("step 2"),
+            "line 1: nop +0 | acc 0\nline 2: acc +1 | acc 1\n"
+        );
+        assert_eq!(execute("b 8"), "breakpoint at line 8\n");
+        assert_eq!(execute("b acc 5"), "breakpoint when acc becomes 5\n");
+        assert_eq!(
+            execute("c"),
+            "line 7: acc +1 | acc 2\nbreakpoint at line 8\n"
+        );
+        assert_eq!(
+            execute("continue"),
+            "line 4: acc +3 | acc 5\nbreakpoint: acc is 5\n"
+        );
+        assert_eq!(execute("loop"), "no loop detected yet\n");
+        assert_eq!(
+            execute("c"),
+            "line 5: jmp -3 | acc 5\nloop detected: line 2 would run a second time, acc is 5\n"
+        );
+        assert_eq!(
+            execute("loop"),
+            "line 2: acc +1 | acc 1\nline 3: jmp +4 | acc 1\nline 7: acc +1 | acc 2\n\
+             line 8: jmp -4 | acc 2\nline 4: acc +3 | acc 5\nline 5: jmp -3 | acc 5\n"
+        );
+        assert_eq!(
+            execute("t 2"),
+            "line 4: acc +3 | acc 5\nline 5: jmp -3 | acc 5\n"
+        );
+        assert_eq!(
+            execute("back 2"),
+            "undid line 5: jmp -3 | acc 5\nundid line 4: acc +3 | acc 5\n\
+             next line 4: acc +3 | acc 2 | 5 steps\n"
+        );
+        assert_eq!(
+            execute("r 9"),
+            "undid line 8: jmp -4 | acc 2\n\
+             undid line 7: acc +1 | acc 2\nundid line 3: jmp +4 | acc 1\n\
+             undid line 2: acc +1 | acc 1\nundid line 1: nop +0 | acc 0\n\
+             at the start of the program\nnext line 1: nop +0 | acc 0 | 0 steps\n"
+        );
+        assert_eq!(execute("breakpoints"), "line 8\nacc 5\n");
+        assert_eq!(execute("d"), "deleted all breakpoints\n");
+        assert!(execute("c").ends_with("loop detected: line 2 would run a second time, acc is 5\n"));
+        assert_eq!(execute("repairs"), "line 8: jmp -> nop, acc 8\n");
+    }
+
+    #[test]
+    fn continue_moves_off_a_breakpoint() {
+        let program = parse(INPUT_RAW).unwrap();
+        let mut debugger = Debugger::new(&program);
+        let mut execute = |command: &str| debugger.execute(command).unwrap();
+        assert_eq!(execute("b 3"), "breakpoint at line 3\n");
+        assert_eq!(
+            execute("c"),
+            "line 2: acc +1 | acc 1\nbreakpoint at line 3\n"
+        );
+        assert!(execute("c").starts_with("line 5: jmp -3 | acc 5\nloop detected"));
+
+        // Stepping onto a breakpoint and then continuing runs past it.
+        let mut debugger = Debugger::new(&program);
+        let mut execute = |command: &str| debugger.execute(command).unwrap();
+        execute("b 3");
+        execute("b 8");
+        assert_eq!(
+            execute("s 2"),
+            "line 1: nop +0 | acc 0\nline 2: acc +1 | acc 1\n"
+        );
+        assert_eq!(
+            execute("c"),
+            "line 7: acc +1 | acc 2\nbreakpoint at line 8\n"
+        );
+        // So does going back onto one, and a breakpoint on the first line does not stop the
+        // start of the run.
+        execute("r 2");
+        assert_eq!(
+            execute("c"),
+            "line 7: acc +1 | acc 2\nbreakpoint at line 8\n"
+        );
+        let mut debugger = Debugger::new(&program);
+        let mut execute = |command: &str| debugger.execute(command).unwrap();
+        execute("b 1");
+        assert!(execute("c").ends_with("loop detected: line 2 would run a second time, acc is 5\n"));
+    }
+
+    #[test]
+    fn control_flow() {
+        let program = parse(INPUT_RAW).unwrap();
+        let mut debugger = Debugger::new(&program);
+        let mut execute = |command: &str| debugger.execute(command).unwrap();
+        execute("s 2");
+        assert_eq!(
+            execute("blocks"),
+            "block 0: lines 1-1 | acc +0 | falls through to block 1\n\
+             block 1: lines 2-3 | acc +1 | jumps to block 4 | in a loop | current\n\
+             block 2: lines 4-5 | acc +3 | jumps to block 1 | in a loop\n\
+             block 3: lines 6-6 | acc -99 | falls through to block 4 | unreachable\n\
+             block 4: lines 7-8 | acc +1 | jumps to block 2 | in a loop\n\
+             block 5: lines 9-9 | acc +6 | falls through to the end | unreachable\n"
+        );
+        assert_eq!(
+            execute("analyze"),
+            "the program loops over lines 2, 3, 7, 8, 4, 5\n\
+             from line 3 it loops over lines 2, 3, 7, 8, 4, 5\n\
+             flipping line 8 from jmp to nop makes it terminate\n"
+        );
+        let dot = execute("dot");
+        assert!(dot.starts_with("digraph program {\n"));
+        assert!(dot.contains("    b4 -> b2;\n    b5 -> exit [style=dashed];\n"));
+    }
+
+    #[test]
+    fn errors_and_exits() {
+        let program = parse("acc +2\njmp -2").unwrap();
+        let mut debugger = Debugger::new(&program);
+        let mut execute = |command: &str| debugger.execute(command).unwrap();
+        assert_eq!(
+            execute("b 3"),
+            "error: line 3 is not in the program, which has 2 lines\n"
+        );
+        assert_eq!(execute("b acc x"), "error: invalid accumulator value 'x'\n");
+        assert_eq!(execute("d 1"), "error: no such breakpoint\n");
+        assert_eq!(
+            execute("jump"),
+            "error: unknown command 'jump', try 'help'\n"
+        );
+        assert_eq!(
+            execute("s 5"),
+            "line 1: acc +2 | acc 2\nline 2: jmp -2 | acc 2\n\
+             jumped to line 0, outside the program, acc is 2\n"
+        );
+        assert_eq!(
+            execute("p"),
+            "at line 0, outside the program | acc 2 | 2 steps\n"
+        );
+        assert_eq!(execute("repairs"), "line 2: jmp -> nop, acc 2\n");
+        assert_eq!(
+            execute("analyze"),
+            "the program jumps to line 0, outside the program\n\
+             from line 0 it jumps to line 0, outside the program\n\
+             flipping line 2 from jmp to nop makes it terminate\n"
+        );
+        assert_eq!(debugger.execute("q"), None);
+        let stuck = parse("jmp +0\njmp -1").unwrap();
+        assert_eq!(
+            Debugger::new(&stuck).execute("repairs").unwrap(),
+            "no single flip makes the program terminate\n"
+        );
+
+        let halting = parse("nop +0\nacc +7").unwrap();
+        let mut output = vec![];
+        run(&halting, "s\nc\nquit\nstep\n".as_bytes(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "(dbg) line 1: nop +0 | acc 0\n\
+             (dbg) line 2: acc +7 | acc 7\nprogram halted with acc 7\n(dbg) "
+        );
+    }
+}
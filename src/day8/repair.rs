@@ -0,0 +1,178 @@
+use std::convert::TryFrom;
+
+use super::vm::{ExitReason, Instruction, InstructionSet, Vm, Word};
+use super::{flipped, next_pc};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repair {
+    pub index: usize,
+    pub from: &'static str,
+    pub to: &'static str,
+    // The accumulator once the repaired program has terminated.
+    pub acc: Word,
+}
+
+fn run_repaired(program: &[Instruction], index: usize, flip: Instruction) -> Option<Repair> {
+    let mut repaired = program.to_vec();
+    let from = repaired[index].mnemonic();
+    let to = flip.mnemonic();
+    repaired[index] = flip;
+    let mut vm = Vm::new(&repaired);
+    match vm.run() {
+        ExitReason::Halted => Some(Repair {
+            index,
+            from,
+            to,
+            acc: vm.state.acc(),
+        }),
+        _ => None,
+    }
+}
+
+// For every instruction, whether running from it ends exactly one past the last instruction.
+// Works backwards from the end over the reversed control flow, so it is linear in the program
+// length.
+pub fn reaches_end(program: &[Instruction]) -> Vec<bool> {
+    let len = program.len();
+    let mut predecessors = vec![vec![]; len + 1];
+    for (pc, instruction) in program.iter().enumerate() {
+        let next = next_pc(instruction, pc);
+        if 0 <= next && next <= len as Word {
+            predecessors[next as usize].push(pc);
+        }
+    }
+    let mut reaches = vec![false; len + 1];
+    reaches[len] = true;
+    let mut stack = vec![len];
+    while let Some(pc) = stack.pop() {
+        for &previous in &predecessors[pc] {
+            if !reaches[previous] {
+                reaches[previous] = true;
+                stack.push(previous);
+            }
+        }
+    }
+    reaches.truncate(len);
+    reaches
+}
+
+// Finds the jmp/nop flip that makes the program terminate by following the original run and
+// flipping the first instruction whose new target is known to reach the end. `None` if the program
+// terminates already or no single flip helps.
+pub fn repair(program: &[Instruction]) -> Option<Repair> {
+    let set = InstructionSet::standard();
+    let reaches = reaches_end(program);
+    let terminates = |pc: Word| {
+        pc == program.len() as Word
+            || usize::try_from(pc).is_ok_and(|pc| reaches.get(pc) == Some(&true))
+    };
+    if terminates(0) {
+        return None;
+    }
+    let mut visited = vec![false; program.len()];
+    let mut pc = 0;
+    while 0 <= pc && (pc as usize) < program.len() && !visited[pc as usize] {
+        let index = pc as usize;
+        visited[index] = true;
+        if let Some(flip) = flipped(&program[index], &set) {
+            if terminates(next_pc(&flip, index)) {
+                return run_repaired(program, index, flip);
+            }
+        }
+        pc = next_pc(&program[index], index);
+    }
+    None
+}
+
+// Reference implementation: tries every flip and runs the whole program each time.
+pub fn repairs_brute_force(program: &[Instruction]) -> Vec<Repair> {
+    let set = InstructionSet::standard();
+    (0..program.len())
+        .filter_map(|index| {
+            let flip = flipped(&program[index], &set)?;
+            run_repaired(program, index, flip)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{reaches_end, repair, repairs_brute_force, Repair};
+    use crate::day8::vm::{ExitReason, Instruction, InstructionSet, Vm};
+
+    // Random programs that mostly jump forwards, with the occasional backward jump to get stuck on.
+    pub fn random_program(seed: u64, len: usize) -> Vec<Instruction> {
+        let set = InstructionSet::standard();
+        let mut state = seed;
+        let mut next = move |n: i64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as i64 % n
+        };
+        (0..len)
+            .map(|_| {
+                let mnemonic = ["acc", "acc", "jmp", "nop"][next(4) as usize];
+                let arg = match next(5) {
+                    0 => -1 - next(6),
+                    _ => 1 + next(6),
+                };
+                Instruction {
+                    op: set.get(mnemonic).unwrap(),
+                    arg,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn repairs_sample() {
+        let program = InstructionSet::standard()
+            .parse("nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6")
+            .unwrap();
+        assert_eq!(
+            reaches_end(&program),
+            vec![false, false, false, false, false, false, false, false, true]
+        );
+        let expected = Repair {
+            index: 7,
+            from: "jmp",
+            to: "nop",
+            acc: 8,
+        };
+        assert_eq!(repair(&program), Some(expected.clone()));
+        assert_eq!(repairs_brute_force(&program), vec![expected]);
+        assert_eq!(repair(&program[8..]), None);
+    }
+
+    #[test]
+    fn leaves_terminating_programs_alone() {
+        let program = InstructionSet::standard().parse("nop +1\nacc +1").unwrap();
+        assert_eq!(Vm::new(&program).run(), ExitReason::Halted);
+        // Flipping the nop to jmp +1 would terminate as well.
+        assert_eq!(repairs_brute_force(&program).len(), 1);
+        assert_eq!(repair(&program), None);
+        assert_eq!(repair(&[]), None);
+    }
+
+    #[test]
+    fn agrees_with_brute_force() {
+        let mut repaired = 0;
+        for seed in 0..300 {
+            let program = random_program(seed, 40);
+            if Vm::new(&program).run() == ExitReason::Halted {
+                assert_eq!(repair(&program), None, "seed {}", seed);
+                continue;
+            }
+            let all = repairs_brute_force(&program);
+            match repair(&program) {
+                Some(found) => {
+                    assert!(all.contains(&found), "seed {}", seed);
+                    repaired += 1;
+                }
+                None => assert!(all.is_empty(), "seed {}", seed),
+            }
+        }
+        assert!(repaired > 10);
+    }
+}
@@ -0,0 +1,519 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::rc::Rc;
+
+pub type Word = i64;
+
+// The accumulator is always register 0.
+pub const ACC: usize = 0;
+
+// What the machine does after an instruction has been executed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flow {
+    Next,
+    // Relative to the executed instruction.
+    Jump(Word),
+    Halt,
+}
+
+pub trait Operation {
+    fn mnemonic(&self) -> &'static str;
+    fn execute(&self, arg: Word, registers: &mut [Word]) -> Flow;
+}
+
+struct Acc;
+
+impl Operation for Acc {
+    fn mnemonic(&self) -> &'static str {
+        "acc"
+    }
+
+    fn execute(&self, arg: Word, registers: &mut [Word]) -> Flow {
+        registers[ACC] = registers[ACC].wrapping_add(arg);
+        Flow::Next
+    }
+}
+
+struct Jmp;
+
+impl Operation for Jmp {
+    fn mnemonic(&self) -> &'static str {
+        "jmp"
+    }
+
+    fn execute(&self, arg: Word, _: &mut [Word]) -> Flow {
+        Flow::Jump(arg)
+    }
+}
+
+struct Nop;
+
+impl Operation for Nop {
+    fn mnemonic(&self) -> &'static str {
+        "nop"
+    }
+
+    fn execute(&self, _: Word, _: &mut [Word]) -> Flow {
+        Flow::Next
+    }
+}
+
+#[derive(Clone)]
+pub struct Instruction {
+    pub op: Rc<dyn Operation>,
+    pub arg: Word,
+}
+
+impl Instruction {
+    pub fn mnemonic(&self) -> &'static str {
+        self.op.mnemonic()
+    }
+}
+
+impl PartialEq for Instruction {
+    fn eq(&self, other: &Self) -> bool {
+        self.mnemonic() == other.mnemonic() && self.arg == other.arg
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {:+}", self.mnemonic(), self.arg)
+    }
+}
+
+impl fmt::Debug for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseErrorKind {
+    MissingOperation,
+    UnknownOperation(String),
+    MissingArgument,
+    InvalidArgument(String),
+    UnexpectedInput(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            ParseErrorKind::MissingOperation => write!(f, "expected an operation"),
+            ParseErrorKind::UnknownOperation(op) => write!(f, "unknown operation '{}'", op),
+            ParseErrorKind::MissingArgument => write!(f, "expected an argument"),
+            ParseErrorKind::InvalidArgument(arg) => write!(f, "invalid argument '{}'", arg),
+            ParseErrorKind::UnexpectedInput(input) => write!(f, "unexpected '{}'", input),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// The operations a program may use, by mnemonic. Registering an operation under a mnemonic that
+// is already taken replaces it.
+#[derive(Clone, Default)]
+pub struct InstructionSet {
+    operations: HashMap<&'static str, Rc<dyn Operation>>,
+}
+
+impl InstructionSet {
+    pub fn new() -> Self {
+        InstructionSet {
+            operations: HashMap::new(),
+        }
+    }
+
+    pub fn standard() -> Self {
+        InstructionSet::new()
+            .register(Acc)
+            .register(Jmp)
+            .register(Nop)
+    }
+
+    pub fn register(mut self, operation: impl Operation + 'static) -> Self {
+        self.operations
+            .insert(operation.mnemonic(), Rc::new(operation));
+        self
+    }
+
+    pub fn get(&self, mnemonic: &str) -> Option<Rc<dyn Operation>> {
+        self.operations.get(mnemonic).cloned()
+    }
+
+    // One `mnemonic argument` instruction per line, the argument being a signed integer.
+    pub fn parse(&self, input: &str) -> Result<Vec<Instruction>, ParseError> {
+        input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                let mut tokens = line.split_whitespace().map(|token| {
+                    let offset = token.as_ptr() as usize - line.as_ptr() as usize;
+                    (line[..offset].chars().count() + 1, token)
+                });
+                let error = |column, kind| ParseError {
+                    line: i + 1,
+                    column,
+                    kind,
+                };
+                let end = line.chars().count() + 1;
+                let (column, mnemonic) = tokens
+                    .next()
+                    .ok_or_else(|| error(end, ParseErrorKind::MissingOperation))?;
+                let op = self.get(mnemonic).ok_or_else(|| {
+                    error(
+                        column,
+                        ParseErrorKind::UnknownOperation(mnemonic.to_string()),
+                    )
+                })?;
+                let (column, arg) = tokens
+                    .next()
+                    .ok_or_else(|| error(end, ParseErrorKind::MissingArgument))?;
+                let arg = arg
+                    .parse::<Word>()
+                    .map_err(|_| error(column, ParseErrorKind::InvalidArgument(arg.to_string())))?;
+                if let Some((column, rest)) = tokens.next() {
+                    return Err(error(
+                        column,
+                        ParseErrorKind::UnexpectedInput(rest.to_string()),
+                    ));
+                }
+                Ok(Instruction { op, arg })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExitReason {
+    // The program counter is just past the last instruction, or an operation halted.
+    Halted,
+    // The instruction at `pc` is about to run a second time.
+    Looping { pc: usize },
+    OutOfBounds { pc: Word },
+    BudgetExhausted,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct State {
+    pub pc: Word,
+    pub registers: Vec<Word>,
+}
+
+impl State {
+    pub fn new(registers: usize) -> Self {
+        State {
+            pc: 0,
+            registers: vec![0; registers.max(1)],
+        }
+    }
+
+    pub fn acc(&self) -> Word {
+        self.registers[ACC]
+    }
+
+    // Executes the instruction at the program counter, or says why there is none.
+    pub fn step(&mut self, program: &[Instruction]) -> Option<ExitReason> {
+        if self.pc == program.len() as Word {
+            return Some(ExitReason::Halted);
+        }
+        if self.pc < 0 || self.pc > program.len() as Word {
+            return Some(ExitReason::OutOfBounds { pc: self.pc });
+        }
+        let instruction = &program[self.pc as usize];
+        match instruction.op.execute(instruction.arg, &mut self.registers) {
+            Flow::Next => self.pc += 1,
+            Flow::Jump(offset) => self.pc = self.pc.saturating_add(offset),
+            Flow::Halt => return Some(ExitReason::Halted),
+        }
+        None
+    }
+}
+
+// One executed instruction. `acc` is the accumulator after it ran, `before` the whole state before
+// it ran so the step can be undone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEntry {
+    pub pc: usize,
+    pub instruction: Instruction,
+    pub acc: Word,
+    pub before: State,
+}
+
+// Runs a program, stopping as soon as an instruction would be executed twice or the step budget
+// is used up.
+pub struct Vm<'a> {
+    program: &'a [Instruction],
+    pub state: State,
+    visited: Vec<bool>,
+    pub steps: usize,
+    budget: Option<usize>,
+    trace: Option<Vec<TraceEntry>>,
+}
+
+impl<'a> Vm<'a> {
+    pub fn new(program: &'a [Instruction]) -> Self {
+        Vm {
+            program,
+            state: State::new(1),
+            visited: vec![false; program.len()],
+            steps: 0,
+            budget: None,
+            trace: None,
+        }
+    }
+
+    pub fn with_registers(mut self, registers: usize) -> Self {
+        self.state = State::new(registers);
+        self
+    }
+
+    pub fn with_budget(mut self, steps: usize) -> Self {
+        self.budget = Some(steps);
+        self
+    }
+
+    // Records every executed instruction from now on.
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(vec![]);
+        self
+    }
+
+    // Empty unless tracing is enabled.
+    pub fn trace(&self) -> &[TraceEntry] {
+        self.trace.as_deref().unwrap_or(&[])
+    }
+
+    pub fn step(&mut self) -> Option<ExitReason> {
+        let index = usize::try_from(self.state.pc)
+            .ok()
+            .filter(|&pc| pc < self.program.len());
+        if let Some(pc) = index {
+            if self.visited[pc] {
+                return Some(ExitReason::Looping { pc });
+            }
+            if self.budget.is_some_and(|budget| self.steps >= budget) {
+                return Some(ExitReason::BudgetExhausted);
+            }
+            self.visited[pc] = true;
+            self.steps += 1;
+        }
+        let before = self.trace.as_ref().map(|_| self.state.clone());
+        let exit = self.state.step(self.program);
+        if let (Some(trace), Some(before), Some(pc)) = (self.trace.as_mut(), before, index) {
+            trace.push(TraceEntry {
+                pc,
+                instruction: self.program[pc].clone(),
+                acc: self.state.acc(),
+                before,
+            });
+        }
+        exit
+    }
+
+    // Undoes the last traced step, returning it. `None` at the start of the trace or when tracing
+    // is disabled.
+    pub fn step_back(&mut self) -> Option<TraceEntry> {
+        let entry = self.trace.as_mut()?.pop()?;
+        self.state = entry.before.clone();
+        self.visited[entry.pc] = false;
+        self.steps -= 1;
+        Some(entry)
+    }
+
+    // The traced steps from the first run of the instruction about to be repeated, once the
+    // machine has stopped because of a loop.
+    pub fn loop_trace(&self) -> Option<&[TraceEntry]> {
+        let pc = usize::try_from(self.state.pc).ok()?;
+        if !*self.visited.get(pc)? {
+            return None;
+        }
+        let trace = self.trace();
+        let start = trace.iter().rposition(|entry| entry.pc == pc)?;
+        Some(&trace[start..])
+    }
+
+    pub fn run(&mut self) -> ExitReason {
+        loop {
+            if let Some(exit) = self.step() {
+                return exit;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{ExitReason, Flow, InstructionSet, Operation, ParseErrorKind, Vm, Word, ACC};
+    use crate::day8::tests::INPUT_RAW;
+
+    struct Mul;
+
+    impl Operation for Mul {
+        fn mnemonic(&self) -> &'static str {
+            "mul"
+        }
+
+        fn execute(&self, arg: Word, registers: &mut [Word]) -> Flow {
+            registers[ACC] *= arg;
+            Flow::Next
+        }
+    }
+
+    // Copies the accumulator into the register given by the argument.
+    struct Sto;
+
+    impl Operation for Sto {
+        fn mnemonic(&self) -> &'static str {
+            "sto"
+        }
+
+        fn execute(&self, arg: Word, registers: &mut [Word]) -> Flow {
+            registers[arg as usize] = registers[ACC];
+            Flow::Next
+        }
+    }
+
+    struct Hlt;
+
+    impl Operation for Hlt {
+        fn mnemonic(&self) -> &'static str {
+            "hlt"
+        }
+
+        fn execute(&self, _: Word, _: &mut [Word]) -> Flow {
+            Flow::Halt
+        }
+    }
+
+    fn extended() -> InstructionSet {
+        InstructionSet::standard()
+            .register(Mul)
+            .register(Sto)
+            .register(Hlt)
+    }
+
+    #[test]
+    fn registered_operations() {
+        let program = extended()
+            .parse("acc +3\nsto +2\nmul +4\nhlt +0\nacc +100")
+            .unwrap();
+        let mut vm = Vm::new(&program).with_registers(3);
+        assert_eq!(vm.run(), ExitReason::Halted);
+        assert_eq!(vm.state.registers, vec![12, 0, 3]);
+        assert_eq!(vm.steps, 4);
+        assert_eq!(program[2].to_string(), "mul +4");
+        assert!(InstructionSet::standard().parse("mul +4").is_err());
+    }
+
+    #[test]
+    fn exit_reasons() {
+        let set = InstructionSet::standard();
+        let run = |input: &str| {
+            let program = set.parse(input).unwrap();
+            let mut vm = Vm::new(&program);
+            (vm.run(), vm.state.acc(), vm.steps)
+        };
+        assert_eq!(run("acc +1\nnop +0"), (ExitReason::Halted, 1, 2));
+        assert_eq!(run("acc +1\njmp -1"), (ExitReason::Looping { pc: 0 }, 1, 2));
+        assert_eq!(
+            run("acc +2\njmp -2"),
+            (ExitReason::OutOfBounds { pc: -1 }, 2, 2)
+        );
+        assert_eq!(
+            run("jmp +3\nnop +0"),
+            (ExitReason::OutOfBounds { pc: 3 }, 0, 1)
+        );
+        assert_eq!(run(""), (ExitReason::Halted, 0, 0));
+    }
+
+    #[test]
+    fn step_budget() {
+        let program = InstructionSet::standard()
+            .parse("acc +1\nacc +1\nacc +1")
+            .unwrap();
+        let mut vm = Vm::new(&program).with_budget(2);
+        assert_eq!(vm.run(), ExitReason::BudgetExhausted);
+        assert_eq!(vm.state.acc(), 2);
+        assert_eq!(vm.state.pc, 2);
+    }
+
+    #[test]
+    fn trace_and_step_back() {
+        let program = InstructionSet::standard().parse(INPUT_RAW).unwrap();
+        let mut vm = Vm::new(&program).with_trace();
+        assert_eq!(vm.run(), ExitReason::Looping { pc: 1 });
+        let executed = |trace: &[super::TraceEntry]| {
+            trace
+                .iter()
+                .map(|entry| (entry.pc, entry.instruction.to_string(), entry.acc))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vm.trace().len(), 7);
+        assert_eq!(
+            executed(vm.loop_trace().unwrap()),
+            vec![
+                (1, "acc +1".to_string(), 1),
+                (2, "jmp +4".to_string(), 1),
+                (6, "acc +1".to_string(), 2),
+                (7, "jmp -4".to_string(), 2),
+                (3, "acc +3".to_string(), 5),
+                (4, "jmp -3".to_string(), 5),
+            ]
+        );
+
+        assert_eq!(vm.step_back().unwrap().pc, 4);
+        assert_eq!(vm.step_back().unwrap().pc, 3);
+        assert_eq!((vm.state.pc, vm.state.acc(), vm.steps), (3, 2, 5));
+        assert_eq!(vm.loop_trace(), None);
+        assert_eq!(vm.step(), None);
+        assert_eq!(vm.state.acc(), 5);
+        assert_eq!(vm.run(), ExitReason::Looping { pc: 1 });
+        assert_eq!(vm.trace().len(), 7);
+
+        let mut untraced = Vm::new(&program);
+        untraced.run();
+        assert!(untraced.trace().is_empty());
+        assert_eq!(untraced.step_back(), None);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let set = InstructionSet::standard();
+        let error = set.parse("nop +0\n  foo +1").unwrap_err();
+        assert_eq!(
+            error.kind,
+            ParseErrorKind::UnknownOperation("foo".to_string())
+        );
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 3: unknown operation 'foo'"
+        );
+        assert_eq!(
+            set.parse("acc").unwrap_err().to_string(),
+            "line 1, column 4: expected an argument"
+        );
+        assert_eq!(
+            set.parse("nop +0\n\nnop +0").unwrap_err().to_string(),
+            "line 2, column 1: expected an operation"
+        );
+        assert_eq!(
+            set.parse("acc 1x").unwrap_err().to_string(),
+            "line 1, column 5: invalid argument '1x'"
+        );
+        assert_eq!(
+            set.parse("acc +1 +2").unwrap_err().kind,
+            ParseErrorKind::UnexpectedInput("+2".to_string())
+        );
+    }
+}
@@ -1,54 +1,120 @@
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+
+pub mod stream;
+
+const DEFAULT_PREAMBLE: usize = 25;
+
 #[aoc_generator(day9)]
 fn generator_input(input: &str) -> Vec<usize> {
     input.lines().map(|a| a.parse::<usize>().unwrap()).collect()
 }
 
-fn number_is_weak(number: usize, preamble: &[usize]) -> bool {
-    for i in 0..preamble.len() - 1 {
-        if preamble[i] <= number {
-            for j in i + 1..preamble.len() {
-                if preamble[i] + preamble[j] == number {
-                    return false;
+// The last `preamble` numbers together with how many pairs of them add up to each sum, so that
+// moving the window along costs one pass over it instead of one over all its pairs.
+pub struct Window {
+    preamble: usize,
+    numbers: VecDeque<usize>,
+    sums: HashMap<usize, usize>,
+}
+
+impl Window {
+    pub fn new(preamble: usize) -> Self {
+        Window {
+            preamble,
+            numbers: VecDeque::with_capacity(preamble + 1),
+            sums: HashMap::new(),
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.numbers.len() == self.preamble
+    }
+
+    // Whether two numbers at different positions in the window add up to `number`.
+    pub fn is_valid(&self, number: usize) -> bool {
+        self.sums.contains_key(&number)
+    }
+
+    // Adds `number`, dropping the oldest number once the window is full.
+    pub fn push(&mut self, number: usize) {
+        if self.is_full() {
+            if let Some(oldest) = self.numbers.pop_front() {
+                for &other in &self.numbers {
+                    let sum = oldest + other;
+                    let count = self.sums.get_mut(&sum).unwrap();
+                    *count -= 1;
+                    if *count == 0 {
+                        self.sums.remove(&sum);
+                    }
                 }
             }
         }
+        if self.preamble > 0 {
+            for &other in &self.numbers {
+                *self.sums.entry(number + other).or_insert(0) += 1;
+            }
+            self.numbers.push_back(number);
+        }
     }
-    true
 }
 
-fn find_weakness(input: &[usize], preamble_length: usize) -> Option<usize> {
-    for i in preamble_length..input.len() {
-        if number_is_weak(input[i], &input[i - preamble_length..i]) {
-            return Some(input[i]);
+// Every number after the preamble that is not the sum of two of the `preamble` numbers before it,
+// with its position.
+fn invalid_numbers(input: &[usize], preamble: usize) -> Vec<(usize, usize)> {
+    let mut window = Window::new(preamble);
+    let mut invalid = vec![];
+    for (i, &number) in input.iter().enumerate() {
+        if window.is_full() && !window.is_valid(number) {
+            invalid.push((i, number));
         }
+        window.push(number);
     }
-    None
+    invalid
 }
 
-#[aoc(day9, part1)]
-fn part1(input: &[usize]) -> usize {
-    find_weakness(input, 25).unwrap()
+fn find_weakness(input: &[usize], preamble_length: usize) -> Option<usize> {
+    invalid_numbers(input, preamble_length)
+        .first()
+        .map(|&(_, number)| number)
 }
 
-fn find_contiguous_sum(input: &[usize], target: usize) -> usize {
-    for i in 0..input.len() {
-        for j in i + 1..input.len() {
-            if input[i..j].iter().sum::<usize>() == target {
-                return input[i..j].iter().max().unwrap() + input[i..j].iter().min().unwrap();
-            }
+// The first range of at least two numbers adding up to `target`, found with two pointers since no
+// number is negative.
+fn contiguous_range(input: &[usize], target: usize) -> Option<Range<usize>> {
+    let mut start = 0;
+    let mut sum = 0;
+    for (end, &number) in input.iter().enumerate() {
+        sum += number;
+        while sum > target {
+            sum -= input[start];
+            start += 1;
+        }
+        if sum == target && end > start {
+            return Some(start..end + 1);
         }
     }
-    return 0;
+    None
+}
+
+fn find_contiguous_sum(input: &[usize], target: usize) -> Option<usize> {
+    let range = &input[contiguous_range(input, target)?];
+    Some(range.iter().min()? + range.iter().max()?)
+}
+
+#[aoc(day9, part1)]
+fn part1(input: &[usize]) -> Option<usize> {
+    find_weakness(input, DEFAULT_PREAMBLE)
 }
 
 #[aoc(day9, part2)]
-fn part2(input: &[usize]) -> usize {
-    find_contiguous_sum(input, find_weakness(input, 25).unwrap())
+fn part2(input: &[usize]) -> Option<usize> {
+    find_contiguous_sum(input, find_weakness(input, DEFAULT_PREAMBLE)?)
 }
 
 #[cfg(test)]
 pub mod tests {
-    use super::{find_contiguous_sum, find_weakness};
+    use super::{contiguous_range, find_contiguous_sum, find_weakness, invalid_numbers, Window};
 
     #[test]
     fn test_find_weakness() {
@@ -67,7 +133,42 @@ pub mod tests {
         ];
         assert_eq!(
             find_contiguous_sum(&input, find_weakness(&input, 5).unwrap()),
-            62
+            Some(62)
         );
+        assert_eq!(contiguous_range(&input, 127), Some(2..6));
+        // A single number is not a range.
+        assert_eq!(contiguous_range(&input, 20), None);
+        assert_eq!(contiguous_range(&[5, 0, 3], 5), Some(0..2));
+        assert_eq!(find_contiguous_sum(&input, 1), None);
+    }
+
+    #[test]
+    fn all_invalid_numbers() {
+        let input = vec![1, 2, 3, 4, 10, 7, 7, 14, 3];
+        assert_eq!(invalid_numbers(&input, 3), vec![(4, 10), (6, 7), (8, 3)]);
+        assert_eq!(
+            invalid_numbers(&input, 2),
+            vec![(3, 4), (4, 10), (5, 7), (6, 7), (8, 3)]
+        );
+        assert_eq!(invalid_numbers(&input[..2], 0), vec![(0, 1), (1, 2)]);
+        assert!(invalid_numbers(&input[..3], 5).is_empty());
+    }
+
+    #[test]
+    fn window_counts_pairs() {
+        let mut window = Window::new(3);
+        for number in [4, 4, 1] {
+            window.push(number);
+        }
+        assert!(window.is_full());
+        assert!(window.is_valid(8));
+        assert!(window.is_valid(5));
+        assert!(!window.is_valid(2));
+        window.push(9);
+        assert!(window.is_valid(5));
+        assert!(!window.is_valid(8));
+        window.push(2);
+        assert!(!window.is_valid(5));
+        assert!(window.is_valid(11));
     }
 }
@@ -0,0 +1,302 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufRead, Lines, Write};
+use std::ops::Range;
+
+use super::{contiguous_range, Window, DEFAULT_PREAMBLE};
+
+pub const USAGE: &str = "usage: xmas [--preamble N] [--ranges LOOKBACK] < numbers
+Reads one number per line and reports every number that is not the sum of two of the N numbers
+before it, 25 by default. With --ranges, also looks for a run of at least two of the LOOKBACK
+numbers before it that adds up to it.";
+
+#[derive(Debug)]
+pub enum StreamError {
+    Usage(String),
+    Io(io::Error),
+    InvalidNumber { line: usize, found: String },
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StreamError::Usage(message) => write!(f, "{}\n{}", message, USAGE),
+            StreamError::Io(e) => write!(f, "{}", e),
+            StreamError::InvalidNumber { line, found } => {
+                write!(f, "line {}: invalid number '{}'", line, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<io::Error> for StreamError {
+    fn from(e: io::Error) -> Self {
+        StreamError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContiguousRange {
+    // Positions in the stream.
+    pub positions: Range<usize>,
+    pub smallest: usize,
+    pub largest: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Invalid {
+    // 0-based, counting numbers rather than lines.
+    pub position: usize,
+    pub number: usize,
+    pub range: Option<ContiguousRange>,
+}
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "position {}: {}", self.position, self.number)?;
+        if let Some(range) = &self.range {
+            write!(
+                f,
+                ", sum of positions {} to {}, weakness {}",
+                range.positions.start,
+                range.positions.end - 1,
+                range.smallest + range.largest
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Reports invalid numbers as they are read. Only the preamble window and, with ranges enabled, the
+// last `lookback` numbers are kept, however long the stream.
+pub struct Checker<R> {
+    lines: Lines<R>,
+    line: usize,
+    position: usize,
+    window: Window,
+    lookback: usize,
+    history: VecDeque<usize>,
+}
+
+impl<R: BufRead> Checker<R> {
+    pub fn new(input: R, preamble: usize) -> Self {
+        Checker {
+            lines: input.lines(),
+            line: 0,
+            position: 0,
+            window: Window::new(preamble),
+            lookback: 0,
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn with_ranges(mut self, lookback: usize) -> Self {
+        self.lookback = lookback;
+        self.history = VecDeque::with_capacity(lookback);
+        self
+    }
+
+    fn range(&mut self, number: usize) -> Option<ContiguousRange> {
+        let first = self.position - self.history.len();
+        let numbers = self.history.make_contiguous();
+        let range = contiguous_range(numbers, number)?;
+        let numbers = &numbers[range.clone()];
+        Some(ContiguousRange {
+            positions: first + range.start..first + range.end,
+            smallest: *numbers.iter().min()?,
+            largest: *numbers.iter().max()?,
+        })
+    }
+
+    // Reads up to the next invalid number.
+    fn next_invalid(&mut self) -> Result<Option<Invalid>, StreamError> {
+        while let Some(line) = self.lines.next() {
+            let line = line?;
+            self.line += 1;
+            let text = line.trim();
+            if text.is_empty() {
+                continue;
+            }
+            let number = text
+                .parse::<usize>()
+                .map_err(|_| StreamError::InvalidNumber {
+                    line: self.line,
+                    found: text.to_string(),
+                })?;
+            let invalid = if self.window.is_full() && !self.window.is_valid(number) {
+                Some(Invalid {
+                    position: self.position,
+                    number,
+                    range: self.range(number),
+                })
+            } else {
+                None
+            };
+            self.window.push(number);
+            if self.lookback > 0 {
+                if self.history.len() == self.lookback {
+                    self.history.pop_front();
+                }
+                self.history.push_back(number);
+            }
+            self.position += 1;
+            if invalid.is_some() {
+                return Ok(invalid);
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<R: BufRead> Iterator for Checker<R> {
+    type Item = Result<Invalid, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_invalid().transpose()
+    }
+}
+
+fn value(flag: &str, value: Option<&String>) -> Result<usize, StreamError> {
+    let value = value.ok_or_else(|| StreamError::Usage(format!("{} needs a value", flag)))?;
+    value
+        .parse()
+        .map_err(|_| StreamError::Usage(format!("invalid value '{}' for {}", value, flag)))
+}
+
+// Checks the numbers in `input`, writing each invalid one to `output` as soon as it is read, and
+// returns how many there were. `args` does not include the program name.
+pub fn run(
+    args: &[String],
+    input: impl BufRead,
+    output: &mut impl Write,
+) -> Result<usize, StreamError> {
+    let mut preamble = DEFAULT_PREAMBLE;
+    let mut lookback = 0;
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--preamble" => preamble = value(flag, args.next())?,
+            "--ranges" => lookback = value(flag, args.next())?,
+            _ => return Err(StreamError::Usage(format!("unknown argument '{}'", flag))),
+        }
+    }
+    let mut count = 0;
+    for invalid in Checker::new(input, preamble).with_ranges(lookback) {
+        writeln!(output, "{}", invalid?)?;
+        output.flush()?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{run, Checker, ContiguousRange, Invalid, StreamError};
+
+    static INPUT_RAW: &str = r#"35
+20
+15
+25
+47
+40
+62
+55
+65
+95
+102
+117
+150
+182
+127
+219
+299
+277
+309
+576
+"#;
+
+    #[test]
+    fn reports_positions_and_ranges() {
+        let invalid = Checker::new(INPUT_RAW.as_bytes(), 5)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            invalid,
+            vec![Invalid {
+                position: 14,
+                number: 127,
+                range: None,
+            }]
+        );
+
+        let mut checker = Checker::new(INPUT_RAW.as_bytes(), 5).with_ranges(12);
+        let first = checker.next().unwrap().unwrap();
+        assert_eq!(
+            first.range,
+            Some(ContiguousRange {
+                positions: 2..6,
+                smallest: 15,
+                largest: 47,
+            })
+        );
+        assert_eq!(
+            first.to_string(),
+            "position 14: 127, sum of positions 2 to 5, weakness 62"
+        );
+        assert!(checker.next().is_none());
+
+        // Positions 2 to 5 are no longer remembered.
+        let mut checker = Checker::new(INPUT_RAW.as_bytes(), 5).with_ranges(10);
+        assert_eq!(checker.next().unwrap().unwrap().range, None);
+    }
+
+    #[test]
+    fn memory_stays_bounded() {
+        let input = (1..=20_000)
+            .map(|n| format!("{}\n", n % 97 + 1))
+            .collect::<String>();
+        let mut checker = Checker::new(input.as_bytes(), 3).with_ranges(8);
+        let mut count = 0;
+        while let Some(invalid) = checker.next() {
+            invalid.unwrap();
+            assert!(checker.history.len() <= 8);
+            count += 1;
+        }
+        assert!(count > 100);
+        assert_eq!(checker.position, 20_000);
+    }
+
+    #[test]
+    fn errors_and_cli() {
+        let mut checker = Checker::new("1\n2\n\n3\nfour\n".as_bytes(), 2);
+        assert!(matches!(
+            checker.next(),
+            Some(Err(StreamError::InvalidNumber { line: 5, .. }))
+        ));
+
+        let args = ["--preamble", "5", "--ranges", "20"]
+            .iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>();
+        let mut output = vec![];
+        assert_eq!(run(&args, INPUT_RAW.as_bytes(), &mut output).unwrap(), 1);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "position 14: 127, sum of positions 2 to 5, weakness 62\n"
+        );
+        assert!(matches!(
+            run(&args[..1], INPUT_RAW.as_bytes(), &mut vec![]),
+            Err(StreamError::Usage(_))
+        ));
+        assert_eq!(
+            run(&["-x".to_string()], INPUT_RAW.as_bytes(), &mut vec![])
+                .unwrap_err()
+                .to_string()
+                .lines()
+                .next(),
+            Some("unknown argument '-x'")
+        );
+    }
+}
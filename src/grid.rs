@@ -0,0 +1,333 @@
+use std::fmt;
+
+pub const DIRECTIONS_8: [(i64, i64); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+// How coordinates outside of the parsed map are resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topology {
+    Bounded,
+    // Wraps horizontally only, like the repeating forest of day 3.
+    Cylinder,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GridError {
+    Empty,
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    UnknownCell {
+        line: usize,
+        column: usize,
+        char: char,
+    },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridError::Empty => write!(f, "grid has no cells"),
+            GridError::RaggedRow {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: expected {} cells, found {}",
+                line, expected, found
+            ),
+            GridError::UnknownCell { line, column, char } => {
+                write!(
+                    f,
+                    "line {}, column {}: unknown cell {:?}",
+                    line, column, char
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    topology: Topology,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn parse<F>(input: &str, topology: Topology, mut to_cell: F) -> Result<Self, GridError>
+    where
+        F: FnMut(char) -> Option<T>,
+    {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = vec![];
+        for (y, line) in input.lines().enumerate() {
+            let row_start = cells.len();
+            for (x, char) in line.chars().enumerate() {
+                match to_cell(char) {
+                    Some(cell) => cells.push(cell),
+                    None => {
+                        return Err(GridError::UnknownCell {
+                            line: y + 1,
+                            column: x + 1,
+                            char,
+                        })
+                    }
+                }
+            }
+            let row_width = cells.len() - row_start;
+            match width {
+                None => width = Some(row_width),
+                Some(expected) if expected != row_width => {
+                    return Err(GridError::RaggedRow {
+                        line: y + 1,
+                        expected,
+                        found: row_width,
+                    })
+                }
+                _ => {}
+            }
+            height += 1;
+        }
+        match width {
+            Some(width) if width > 0 => Ok(Grid {
+                width,
+                height,
+                topology,
+                cells,
+            }),
+            _ => Err(GridError::Empty),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // Maps (possibly out of range) coordinates onto a cell according to the topology.
+    pub fn resolve(&self, x: i64, y: i64) -> Option<(usize, usize)> {
+        let (width, height) = (self.width as i64, self.height as i64);
+        let (x, y) = match self.topology {
+            Topology::Bounded => (x, y),
+            Topology::Cylinder => (x.rem_euclid(width), y),
+        };
+        if (0..width).contains(&x) && (0..height).contains(&y) {
+            Some((x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> Option<&T> {
+        self.resolve(x, y)
+            .map(|(x, y)| &self.cells[y * self.width + x])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| ((i % width, i / width), cell))
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn map<U, F>(&self, mut f: F) -> Grid<U>
+    where
+        F: FnMut((usize, usize), &T) -> U,
+    {
+        Grid {
+            width: self.width,
+            height: self.height,
+            topology: self.topology,
+            cells: self.iter().map(|(pos, cell)| f(pos, cell)).collect(),
+        }
+    }
+
+    fn offsets<'a>(
+        &'a self,
+        (x, y): (usize, usize),
+        directions: &'a [(i64, i64)],
+    ) -> impl Iterator<Item = ((usize, usize), &'a T)> + 'a {
+        directions.iter().filter_map(move |(dx, dy)| {
+            let pos = self.resolve(x as i64 + dx, y as i64 + dy)?;
+            Some((pos, &self.cells[pos.1 * self.width + pos.0]))
+        })
+    }
+
+    pub fn neighbors8(&self, pos: (usize, usize)) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.offsets(pos, &DIRECTIONS_8)
+    }
+
+    // Walks from `pos` in steps of `direction`, excluding the start. The ray ends at the border
+    // of a bounded axis or once it wraps back onto the start.
+    pub fn ray(&self, pos: (usize, usize), direction: (i64, i64)) -> Ray<'_, T> {
+        Ray {
+            grid: self,
+            start: pos,
+            current: (pos.0 as i64, pos.1 as i64),
+            direction,
+            done: direction == (0, 0),
+        }
+    }
+}
+
+pub struct Ray<'a, T> {
+    grid: &'a Grid<T>,
+    start: (usize, usize),
+    current: (i64, i64),
+    direction: (i64, i64),
+    done: bool,
+}
+
+impl<'a, T> Iterator for Ray<'a, T> {
+    type Item = ((usize, usize), &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let (x, y) = (
+            self.current.0 + self.direction.0,
+            self.current.1 + self.direction.1,
+        );
+        let pos = self.grid.resolve(x, y).filter(|&pos| pos != self.start);
+        match pos {
+            Some(pos) => {
+                // Keep the coordinates normalised so long rays on wrapping grids cannot overflow.
+                self.current = (pos.0 as i64, pos.1 as i64);
+                Some((pos, &self.grid.cells[pos.1 * self.grid.width + pos.0]))
+            }
+            None => {
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (y, row) in self.cells.chunks(self.width).enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{Grid, GridError, Topology};
+
+    static INPUT_RAW: &str = "abc\ndef\nghi";
+
+    fn letters(topology: Topology) -> Grid<char> {
+        Grid::parse(INPUT_RAW, topology, Some).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let grid = letters(Topology::Bounded);
+        assert_eq!(grid.width(), 3);
+        assert_eq!(grid.height(), 3);
+        assert_eq!(grid.get(1, 2), Some(&'h'));
+        assert_eq!(grid.to_string(), INPUT_RAW);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let digit = |c: char| c.to_digit(10);
+        assert_eq!(
+            Grid::parse("", Topology::Bounded, digit),
+            Err(GridError::Empty)
+        );
+        assert_eq!(
+            Grid::parse("12\n345", Topology::Bounded, digit),
+            Err(GridError::RaggedRow {
+                line: 2,
+                expected: 2,
+                found: 3
+            })
+        );
+        assert_eq!(
+            Grid::parse("12\n3x", Topology::Bounded, digit),
+            Err(GridError::UnknownCell {
+                line: 2,
+                column: 2,
+                char: 'x'
+            })
+        );
+    }
+
+    #[test]
+    fn topologies() {
+        let bounded = letters(Topology::Bounded);
+        assert_eq!(bounded.get(3, 0), None);
+        assert_eq!(bounded.get(-1, 0), None);
+
+        let cylinder = letters(Topology::Cylinder);
+        assert_eq!(cylinder.get(3, 0), Some(&'a'));
+        assert_eq!(cylinder.get(-1, 1), Some(&'f'));
+        assert_eq!(cylinder.get(0, 3), None);
+    }
+
+    #[test]
+    fn neighbors() {
+        let grid = letters(Topology::Bounded);
+        let around = |cells: Vec<((usize, usize), &char)>| {
+            cells.into_iter().map(|(_, &c)| c).collect::<String>()
+        };
+        assert_eq!(around(grid.neighbors8((1, 1)).collect()), "bcfihgda");
+        assert_eq!(around(grid.neighbors8((0, 0)).collect()), "bed");
+        let cylinder = letters(Topology::Cylinder);
+        assert_eq!(around(cylinder.neighbors8((0, 0)).collect()), "bedfc");
+    }
+
+    #[test]
+    fn rays() {
+        let grid = letters(Topology::Bounded);
+        let ray =
+            |grid: &Grid<char>, pos, dir| grid.ray(pos, dir).map(|(_, &c)| c).collect::<String>();
+        assert_eq!(ray(&grid, (0, 0), (1, 1)), "ei");
+        assert_eq!(ray(&grid, (1, 2), (0, -1)), "eb");
+        assert_eq!(ray(&grid, (2, 2), (1, 0)), "");
+        let cylinder = letters(Topology::Cylinder);
+        assert_eq!(ray(&cylinder, (1, 0), (1, 0)), "ca");
+        assert_eq!(ray(&cylinder, (2, 0), (1, 1)), "dh");
+    }
+
+    #[test]
+    fn map_cells() {
+        let grid = letters(Topology::Cylinder).map(|(x, y), _| x + y);
+        assert_eq!(grid.get(-1, 0), Some(&2));
+        assert_eq!(grid.to_string(), "012\n123\n234");
+        assert_eq!(grid.cells().sum::<usize>(), 18);
+    }
+}
@@ -20,5 +20,19 @@ mod day6;
 mod day7;
 mod day8;
 mod day9;
+mod grid;
+mod records;
+
+pub use day1::subset_sum as day1_subset_sum;
+pub use day10::chains as day10_chains;
+pub use day2::cli as day2_cli;
+pub use day3::slopes as day3_slopes;
+pub use day4::report as day4_report;
+pub use day5::cli as day5_cli;
+pub use day6::cli as day6_cli;
+pub use day7::cli as day7_cli;
+pub use day8::debugger as day8_debugger;
+pub use day8::vm as day8_vm;
+pub use day9::stream as day9_stream;
 
 aoc_lib! { year = 2020 }
@@ -0,0 +1,311 @@
+use std::fmt;
+
+// A run of non-blank lines; `first_line` is 1-based.
+#[derive(Debug, PartialEq)]
+pub struct Group<'a> {
+    pub first_line: usize,
+    pub lines: Vec<&'a str>,
+}
+
+// Splits the input at blank lines. Several blank lines in a row count as one separator.
+pub fn groups(input: &str) -> Vec<Group<'_>> {
+    let mut groups: Vec<Group> = vec![];
+    let mut in_group = false;
+    for (i, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            in_group = false;
+            continue;
+        }
+        if !in_group {
+            groups.push(Group {
+                first_line: i + 1,
+                lines: vec![],
+            });
+            in_group = true;
+        }
+        groups.last_mut().unwrap().lines.push(line);
+    }
+    groups
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DuplicateKeys {
+    Error,
+    KeepFirst,
+    KeepLast,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub key: String,
+    pub value: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Record {
+    pub first_line: usize,
+    pub fields: Vec<Field>,
+}
+
+impl Record {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|f| f.key == key)
+            .map(|f| f.value.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RecordErrorKind {
+    MissingColon,
+    EmptyKey,
+    DuplicateKey {
+        key: String,
+        first_line: usize,
+        first_column: usize,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RecordError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: RecordErrorKind,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            RecordErrorKind::MissingColon => write!(f, "expected 'key:value'"),
+            RecordErrorKind::EmptyKey => write!(f, "expected a key before ':'"),
+            RecordErrorKind::DuplicateKey {
+                key,
+                first_line,
+                first_column,
+            } => write!(
+                f,
+                "duplicate key '{}', first defined at line {}, column {}",
+                key, first_line, first_column
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+#[derive(Debug, PartialEq)]
+pub struct ParsedRecords {
+    pub records: Vec<Record>,
+    // Fields whose key is not in the parser's known keys. They are still kept in their record.
+    pub unknown: Vec<Field>,
+}
+
+// Parses blank-line separated records of whitespace separated `key:value` pairs. The value is
+// everything after the first ':' up to the next whitespace and is taken verbatim.
+pub struct RecordParser<'a> {
+    duplicates: DuplicateKeys,
+    known_keys: Option<&'a [&'a str]>,
+}
+
+impl<'a> RecordParser<'a> {
+    pub fn new(duplicates: DuplicateKeys) -> Self {
+        RecordParser {
+            duplicates,
+            known_keys: None,
+        }
+    }
+
+    pub fn known_keys(mut self, keys: &'a [&'a str]) -> Self {
+        self.known_keys = Some(keys);
+        self
+    }
+
+    fn parse_field(token: &str, line: usize, column: usize) -> Result<Field, RecordError> {
+        let error = |kind| RecordError { line, column, kind };
+        let colon = token
+            .find(':')
+            .ok_or_else(|| error(RecordErrorKind::MissingColon))?;
+        if colon == 0 {
+            return Err(error(RecordErrorKind::EmptyKey));
+        }
+        Ok(Field {
+            key: token[..colon].to_string(),
+            value: token[colon + 1..].to_string(),
+            line,
+            column,
+        })
+    }
+
+    fn add_field(&self, fields: &mut Vec<Field>, field: Field) -> Result<(), RecordError> {
+        match fields.iter().position(|f| f.key == field.key) {
+            None => fields.push(field),
+            Some(i) => match self.duplicates {
+                DuplicateKeys::Error => {
+                    return Err(RecordError {
+                        line: field.line,
+                        column: field.column,
+                        kind: RecordErrorKind::DuplicateKey {
+                            key: field.key,
+                            first_line: fields[i].line,
+                            first_column: fields[i].column,
+                        },
+                    })
+                }
+                DuplicateKeys::KeepFirst => {}
+                DuplicateKeys::KeepLast => fields[i] = field,
+            },
+        }
+        Ok(())
+    }
+
+    pub fn parse(&self, input: &str) -> Result<ParsedRecords, RecordError> {
+        let mut records = vec![];
+        let mut unknown = vec![];
+        for group in groups(input) {
+            let mut fields = vec![];
+            for (offset, line) in group.lines.iter().enumerate() {
+                let line_number = group.first_line + offset;
+                // The column and byte offset where the current token starts.
+                let mut token_start = None;
+                // A trailing space closes the last token, so it is handled inside the loop.
+                for (column, (i, c)) in line
+                    .char_indices()
+                    .chain(Some((line.len(), ' ')))
+                    .enumerate()
+                {
+                    match (c.is_whitespace(), token_start) {
+                        (false, None) => token_start = Some((column, i)),
+                        (true, Some((start_column, start))) => {
+                            let field =
+                                Self::parse_field(&line[start..i], line_number, start_column + 1)?;
+                            if let Some(known) = self.known_keys {
+                                if !known.contains(&field.key.as_str()) {
+                                    unknown.push(field.clone());
+                                }
+                            }
+                            self.add_field(&mut fields, field)?;
+                            token_start = None;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            records.push(Record {
+                first_line: group.first_line,
+                fields,
+            });
+        }
+        Ok(ParsedRecords { records, unknown })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{groups, DuplicateKeys, Field, RecordError, RecordErrorKind, RecordParser};
+
+    static INPUT_RAW: &str = "a:1 b:2\nc:x:y\n\n\n  d:\"quoted\\\" e:\\\n\nf:3";
+
+    #[test]
+    fn split_groups() {
+        let groups = groups(INPUT_RAW);
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].first_line, 1);
+        assert_eq!(groups[0].lines, vec!["a:1 b:2", "c:x:y"]);
+        assert_eq!(groups[1].first_line, 5);
+        assert_eq!(groups[2].first_line, 7);
+    }
+
+    #[test]
+    fn parse_records() {
+        let parsed = RecordParser::new(DuplicateKeys::Error)
+            .parse(INPUT_RAW)
+            .unwrap();
+        assert_eq!(parsed.records.len(), 3);
+        assert_eq!(parsed.records[0].get("c"), Some("x:y"));
+        assert_eq!(parsed.records[1].get("d"), Some("\"quoted\\\""));
+        assert_eq!(parsed.records[1].get("e"), Some("\\"));
+        assert_eq!(
+            parsed.records[1].fields[1],
+            Field {
+                key: "e".to_string(),
+                value: "\\".to_string(),
+                line: 5,
+                column: 15,
+            }
+        );
+        assert_eq!(parsed.records[2].get("f"), Some("3"));
+        assert_eq!(parsed.records[2].get("a"), None);
+        assert!(parsed.unknown.is_empty());
+    }
+
+    #[test]
+    fn duplicate_key_policies() {
+        let input = "a:1 b:2\nb:3";
+        let err = RecordParser::new(DuplicateKeys::Error)
+            .parse(input)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            RecordError {
+                line: 2,
+                column: 1,
+                kind: RecordErrorKind::DuplicateKey {
+                    key: "b".to_string(),
+                    first_line: 1,
+                    first_column: 5,
+                },
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 1: duplicate key 'b', first defined at line 1, column 5"
+        );
+        let first = RecordParser::new(DuplicateKeys::KeepFirst)
+            .parse(input)
+            .unwrap();
+        assert_eq!(first.records[0].get("b"), Some("2"));
+        let last = RecordParser::new(DuplicateKeys::KeepLast)
+            .parse(input)
+            .unwrap();
+        assert_eq!(last.records[0].get("b"), Some("3"));
+        assert_eq!(last.records[0].fields.len(), 2);
+    }
+
+    #[test]
+    fn reports_unknown_keys() {
+        let parsed = RecordParser::new(DuplicateKeys::Error)
+            .known_keys(&["a", "b"])
+            .parse("a:1 z:2\n\nb:3 y:4")
+            .unwrap();
+        assert_eq!(
+            parsed
+                .unknown
+                .iter()
+                .map(|f| (f.key.as_str(), f.line, f.column))
+                .collect::<Vec<_>>(),
+            vec![("z", 1, 5), ("y", 3, 5)]
+        );
+        assert_eq!(parsed.records[0].get("z"), Some("2"));
+    }
+
+    #[test]
+    fn malformed_fields() {
+        let parser = RecordParser::new(DuplicateKeys::Error);
+        assert_eq!(
+            parser.parse("a:1\nb:2  cc").unwrap_err(),
+            RecordError {
+                line: 2,
+                column: 6,
+                kind: RecordErrorKind::MissingColon,
+            }
+        );
+        assert_eq!(
+            parser.parse(":1").unwrap_err().to_string(),
+            "line 1, column 1: expected a key before ':'"
+        );
+    }
+}